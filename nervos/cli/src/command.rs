@@ -0,0 +1,293 @@
+use crate::error::Error;
+use crate::snapshot::Snapshot;
+use cdm_sdk::script_utils::{class::Class, helper::to_dyn_vec};
+use cdm_sdk::CdmTxBuilder;
+use ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+use std::path::PathBuf;
+
+const DEFAULT_FEE: u64 = 1_000;
+const DEFAULT_CONTRACTS_DIR: &str = "build/release";
+
+pub const USAGE: &str = "\
+Usage: cdm <command> --snapshot <file> [options]
+
+Commands:
+  create-issuer  --name <text> [--owner <script>]
+  create-class   --issuer <out_point> --name <text> --cost <ckb> [--description <text>]
+                 [--renderer <text>] [--metadata-type-hash <hash>]
+  mint           --class <out_point> --content <text>... [--metadata <out_point>] [--to <script>]
+  transfer       --nft <out_point> --to <script>
+  burn           --nft <out_point>
+
+Options:
+  --snapshot <file>       live cells to compose the transaction from
+  --funding <out_point>   funding cell, every cell of the snapshot without type and data
+                          by default, can be repeated
+  --fee <shannons>        transaction fee, 1000 by default
+  --contracts <dir>       contract binaries missing from the snapshot, build/release by
+                          default
+  --no-verify             print the transaction without verifying it
+
+<out_point> is tx_hash:index and <script> is code_hash:hash_type:args, hash_type being
+data, data1 or type. Owners and receivers are the lock of the first funding cell by
+default.";
+
+pub enum Command {
+    CreateIssuer {
+        name: Vec<u8>,
+        owner: Option<Script>,
+    },
+    CreateClass {
+        issuer: OutPoint,
+        class: Class,
+    },
+    Mint {
+        class: OutPoint,
+        metadata: Option<OutPoint>,
+        contents: Vec<Vec<u8>>,
+        receiver: Option<Script>,
+    },
+    Transfer {
+        nft: OutPoint,
+        receiver: Script,
+    },
+    Burn {
+        nft: OutPoint,
+    },
+}
+
+pub struct Options {
+    pub snapshot: PathBuf,
+    pub funding: Vec<OutPoint>,
+    pub fee: u64,
+    pub contracts: PathBuf,
+    pub verify: bool,
+}
+
+// Options in command line order, flags have no value
+struct Args(Vec<(String, Option<String>)>);
+
+impl Args {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, Error> {
+        let mut options = Vec::new();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| Error::Usage(format!("unexpected argument {}", arg)))?;
+            let value = match name {
+                "no-verify" => None,
+                _ => Some(
+                    args.next()
+                        .ok_or_else(|| Error::Usage(format!("missing value of --{}", name)))?,
+                ),
+            };
+            options.push((name.to_string(), value));
+        }
+        Ok(Args(options))
+    }
+
+    fn values(&self, name: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(option, _)| option == name)
+            .filter_map(|(_, value)| value.as_deref())
+            .collect()
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values(name).last().copied()
+    }
+
+    fn required(&self, name: &str) -> Result<&str, Error> {
+        self.value(name)
+            .ok_or_else(|| Error::Usage(format!("missing --{}", name)))
+    }
+
+    fn has_flag(&self, name: &str) -> bool {
+        self.0.iter().any(|(option, _)| option == name)
+    }
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>, Error> {
+    hex::decode(value.trim_start_matches("0x"))
+        .map_err(|_| Error::Usage(format!("invalid hex {}", value)))
+}
+
+fn parse_hash(value: &str) -> Result<[u8; 32], Error> {
+    let bytes = parse_hex(value)?;
+    if bytes.len() != 32 {
+        return Err(Error::Usage(format!("invalid hash {}", value)));
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&bytes);
+    Ok(hash)
+}
+
+fn parse_u64(value: &str) -> Result<u64, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::Usage(format!("invalid number {}", value)))
+}
+
+/// Parses tx_hash:index
+pub fn parse_out_point(value: &str) -> Result<OutPoint, Error> {
+    let invalid = || Error::Usage(format!("invalid out point {}", value));
+    let mut parts = value.split(':');
+    let (tx_hash, index) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(tx_hash), Some(index), None) => (tx_hash, index),
+        _ => return Err(invalid()),
+    };
+    let index: u32 = index.parse().map_err(|_| invalid())?;
+    Ok(OutPoint::new(parse_hash(tx_hash)?.pack(), index))
+}
+
+/// Parses code_hash:hash_type:args
+pub fn parse_script(value: &str) -> Result<Script, Error> {
+    let mut parts = value.split(':');
+    let (code_hash, hash_type, args) =
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(code_hash), Some(hash_type), Some(args), None) => (code_hash, hash_type, args),
+            _ => return Err(Error::Usage(format!("invalid script {}", value))),
+        };
+    let hash_type: u8 = match hash_type {
+        "data" => 0,
+        "type" => 1,
+        "data1" => 2,
+        _ => return Err(Error::Usage(format!("invalid hash type {}", hash_type))),
+    };
+    Ok(Script::new_builder()
+        .code_hash(parse_hash(code_hash)?.pack())
+        .hash_type(Byte::new(hash_type))
+        .args(Bytes::from(parse_hex(args)?).pack())
+        .build())
+}
+
+/// Parses the arguments following the program name
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<(Command, Options), Error> {
+    let mut args = args.into_iter();
+    let command = args
+        .next()
+        .ok_or_else(|| Error::Usage("missing command".to_string()))?;
+    let args = Args::parse(args)?;
+
+    let command = match command.as_str() {
+        "create-issuer" => Command::CreateIssuer {
+            name: args.required("name")?.as_bytes().to_vec(),
+            owner: args.value("owner").map(parse_script).transpose()?,
+        },
+        "create-class" => {
+            let metadata_type_hash = match args.value("metadata-type-hash") {
+                Some(hash) => parse_hash(hash)?.to_vec(),
+                None => Vec::new(),
+            };
+            let renderer = args.value("renderer").unwrap_or_default();
+            Command::CreateClass {
+                issuer: parse_out_point(args.required("issuer")?)?,
+                class: Class {
+                    version: 0,
+                    cost: parse_u64(args.required("cost")?)?,
+                    name: to_dyn_vec(args.required("name")?.as_bytes()),
+                    description: to_dyn_vec(
+                        args.value("description").unwrap_or_default().as_bytes(),
+                    ),
+                    meta_data_cell_type_hash: to_dyn_vec(&metadata_type_hash),
+                    // An empty renderer is left out of the class data
+                    renderer: match renderer {
+                        "" => Vec::new(),
+                        _ => to_dyn_vec(renderer.as_bytes()),
+                    },
+                    flags: 0,
+                    owner_lock_hash: None,
+                },
+            }
+        }
+        "mint" => {
+            let contents: Vec<Vec<u8>> = args
+                .values("content")
+                .into_iter()
+                .map(|content| content.as_bytes().to_vec())
+                .collect();
+            if contents.is_empty() {
+                return Err(Error::Usage("missing --content".to_string()));
+            }
+            Command::Mint {
+                class: parse_out_point(args.required("class")?)?,
+                metadata: args.value("metadata").map(parse_out_point).transpose()?,
+                contents,
+                receiver: args.value("to").map(parse_script).transpose()?,
+            }
+        }
+        "transfer" => Command::Transfer {
+            nft: parse_out_point(args.required("nft")?)?,
+            receiver: parse_script(args.required("to")?)?,
+        },
+        "burn" => Command::Burn {
+            nft: parse_out_point(args.required("nft")?)?,
+        },
+        _ => return Err(Error::Usage(format!("unknown command {}", command))),
+    };
+
+    let options = Options {
+        snapshot: PathBuf::from(args.required("snapshot")?),
+        funding: args
+            .values("funding")
+            .into_iter()
+            .map(parse_out_point)
+            .collect::<Result<_, _>>()?,
+        fee: args
+            .value("fee")
+            .map(parse_u64)
+            .transpose()?
+            .unwrap_or(DEFAULT_FEE),
+        contracts: PathBuf::from(args.value("contracts").unwrap_or(DEFAULT_CONTRACTS_DIR)),
+        verify: !args.has_flag("no-verify"),
+    };
+    Ok((command, options))
+}
+
+/// Builds the unsigned transaction of the command, with the lock deps of the snapshot
+pub fn compose(
+    snapshot: &Snapshot,
+    command: &Command,
+    funding_cells: &[OutPoint],
+    fee: u64,
+) -> Result<TransactionView, Error> {
+    let funding = snapshot.funding(funding_cells, fee)?;
+    let builder = CdmTxBuilder::new(&snapshot.deployment);
+    let default_lock = funding.change_lock.clone();
+    let tx = match command {
+        Command::CreateIssuer { name, owner } => {
+            builder.create_issuer(name, owner.clone().unwrap_or(default_lock), &funding)?
+        }
+        Command::CreateClass { issuer, class } => {
+            builder.create_class(snapshot.cell(issuer)?, class, &funding)?
+        }
+        Command::Mint {
+            class,
+            metadata,
+            contents,
+            receiver,
+        } => {
+            let metadata = metadata
+                .as_ref()
+                .map(|metadata| snapshot.cell(metadata))
+                .transpose()?;
+            let contents: Vec<&[u8]> = contents.iter().map(|content| &content[..]).collect();
+            builder.mint(
+                snapshot.cell(class)?,
+                metadata,
+                &contents,
+                receiver.clone().unwrap_or(default_lock),
+                &funding,
+            )?
+        }
+        Command::Transfer { nft, receiver } => {
+            builder.transfer(snapshot.cell(nft)?, receiver.clone(), &funding)?
+        }
+        Command::Burn { nft } => builder.burn(snapshot.cell(nft)?, &funding)?,
+    };
+    Ok(tx
+        .as_advanced_builder()
+        .cell_deps(snapshot.lock_deps.clone())
+        .build())
+}
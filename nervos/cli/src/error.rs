@@ -0,0 +1,61 @@
+use ckb_types::packed::Script;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Command line not understood, the message is printed with the usage
+    Usage(String),
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The snapshot is missing a cell or holds invalid data
+    Snapshot(String),
+    Sdk(cdm_sdk::Error),
+    /// A type script group rejected the transaction, code is the exit code of the script
+    /// when it ran to completion
+    Verification {
+        script: Script,
+        code: Option<i8>,
+        message: String,
+    },
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<cdm_sdk::Error> for Error {
+    fn from(err: cdm_sdk::Error) -> Self {
+        Error::Sdk(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage(message) => write!(f, "{}", message),
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Json(err) => write!(f, "invalid json: {}", err),
+            Error::Snapshot(message) => write!(f, "invalid snapshot: {}", message),
+            Error::Sdk(err) => write!(f, "{}", err),
+            Error::Verification {
+                script, message, ..
+            } => write!(
+                f,
+                "verification failed: type script 0x{}: {}",
+                hex::encode(script.calc_script_hash().as_slice()),
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
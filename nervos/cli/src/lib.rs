@@ -0,0 +1,15 @@
+//! Offline composition of CDM transactions from a snapshot of live cells. The
+//! transactions are built by `cdm-sdk`, printed as ckb-debugger mock transactions and
+//! verified against the contracts before they are signed.
+
+mod command;
+mod error;
+mod mock_tx;
+mod snapshot;
+mod verify;
+
+pub use command::{compose, parse_args, parse_out_point, parse_script, Command, Options, USAGE};
+pub use error::Error;
+pub use mock_tx::MockTx;
+pub use snapshot::{format_out_point, Snapshot, CONTRACT_NAMES};
+pub use verify::{verify, MAX_CYCLES};
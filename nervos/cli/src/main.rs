@@ -0,0 +1,41 @@
+use cdm::{compose, parse_args, verify, Error, MockTx, Snapshot, MAX_CYCLES, USAGE};
+use ckb_types::prelude::*;
+use std::env;
+use std::process;
+
+fn run() -> Result<(), Error> {
+    let (command, options) = parse_args(env::args().skip(1))?;
+    let mut snapshot = Snapshot::load(&options.snapshot)?;
+    let tx = compose(&snapshot, &command, &options.funding, options.fee)?;
+    // The mock transaction carries the code cells
+    snapshot.load_contracts(&options.contracts)?;
+    let mock_tx = MockTx::resolve(&snapshot, tx)?;
+    println!("{}", serde_json::to_string_pretty(&mock_tx.to_json())?);
+
+    if options.verify {
+        let cycles = verify(&mock_tx, MAX_CYCLES)?;
+        for (type_script, group_cycles) in &cycles {
+            let script_hash = type_script.calc_script_hash();
+            eprintln!(
+                "0x{}: {} cycles",
+                hex::encode(script_hash.as_slice()),
+                group_cycles
+            );
+        }
+        eprintln!(
+            "verified {} type script groups, lock scripts are not run",
+            cycles.len()
+        );
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        if let Error::Usage(_) = err {
+            eprintln!("\n{}", USAGE);
+        }
+        process::exit(1);
+    }
+}
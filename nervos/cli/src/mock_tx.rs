@@ -0,0 +1,106 @@
+use crate::error::Error;
+use crate::snapshot::{format_out_point, Snapshot};
+use cdm_sdk::LiveCell;
+use ckb_jsonrpc_types as json_types;
+use ckb_types::{
+    core::{DepType, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use serde_json::{json, Value};
+
+/// Transaction with the snapshot cells it spends and references, as ckb-debugger and the
+/// local verification need them
+pub struct MockTx {
+    pub tx: TransactionView,
+    pub inputs: Vec<LiveCell>,
+    /// Code cells, from the cell deps of the transaction or listed by its dep groups
+    pub cell_deps: Vec<(CellDep, LiveCell)>,
+    pub dep_groups: Vec<LiveCell>,
+}
+
+impl MockTx {
+    pub fn resolve(snapshot: &Snapshot, tx: TransactionView) -> Result<Self, Error> {
+        let inputs = tx
+            .inputs()
+            .into_iter()
+            .map(|input| snapshot.cell(&input.previous_output()).map(Clone::clone))
+            .collect::<Result<_, _>>()?;
+        let mut cell_deps = Vec::new();
+        let mut dep_groups = Vec::new();
+        for cell_dep in tx.cell_deps().into_iter() {
+            let cell = snapshot.cell(&cell_dep.out_point())?.clone();
+            if !is_dep_group(&cell_dep) {
+                cell_deps.push((cell_dep, cell));
+                continue;
+            }
+            let out_points = OutPointVec::from_slice(&cell.data).map_err(|_| {
+                Error::Snapshot(format!(
+                    "dep group {} is not a list of out points",
+                    format_out_point(&cell.out_point)
+                ))
+            })?;
+            for out_point in out_points.into_iter() {
+                let member = snapshot.cell(&out_point)?.clone();
+                let member_dep = CellDep::new_builder()
+                    .out_point(out_point)
+                    .dep_type(DepType::Code.into())
+                    .build();
+                cell_deps.push((member_dep, member));
+            }
+            dep_groups.push(cell);
+        }
+        Ok(MockTx {
+            tx,
+            inputs,
+            cell_deps,
+            dep_groups,
+        })
+    }
+
+    /// The mock transaction format of ckb-debugger, dep groups are listed with the cells
+    /// they expand to. Headers are not included, the contracts do not load them.
+    pub fn to_json(&self) -> Value {
+        let inputs: Vec<Value> = self
+            .tx
+            .inputs()
+            .into_iter()
+            .zip(self.inputs.iter())
+            .map(|(input, cell)| {
+                mock_cell("input", json!(json_types::CellInput::from(input)), cell)
+            })
+            .collect();
+        let group_deps = self.tx.cell_deps().into_iter().filter(is_dep_group);
+        let cell_deps: Vec<Value> = self
+            .cell_deps
+            .iter()
+            .map(|(cell_dep, cell)| (cell_dep.clone(), cell))
+            .chain(group_deps.zip(self.dep_groups.iter()))
+            .map(|(cell_dep, cell)| {
+                mock_cell("cell_dep", json!(json_types::CellDep::from(cell_dep)), cell)
+            })
+            .collect();
+        json!({
+            "mock_info": {
+                "inputs": inputs,
+                "cell_deps": cell_deps,
+                "header_deps": [],
+            },
+            "tx": json_types::Transaction::from(self.tx.data()),
+        })
+    }
+}
+
+fn is_dep_group(cell_dep: &CellDep) -> bool {
+    let dep_group: Byte = DepType::DepGroup.into();
+    cell_dep.dep_type() == dep_group
+}
+
+fn mock_cell(key: &str, value: Value, cell: &LiveCell) -> Value {
+    json!({
+        key: value,
+        "output": json_types::CellOutput::from(cell.output.clone()),
+        "data": json_types::JsonBytes::from_bytes(cell.data.clone()),
+        "header": null,
+    })
+}
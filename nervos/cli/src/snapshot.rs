@@ -0,0 +1,199 @@
+//! Live cells the transactions are composed from, as JSON:
+//!
+//! ```json
+//! {
+//!   "deployment": {
+//!     "issuer_type": { "code_hash": "0x..", "hash_type": "data", "cell_dep": { .. } },
+//!     "class_type": { .. },
+//!     "nft_type": { .. },
+//!     "metadata_type": { .. },
+//!     "registry": { "out_point": { .. }, "dep_type": "code" }
+//!   },
+//!   "cells": [{ "out_point": { .. }, "output": { .. }, "data": "0x" }],
+//!   "lock_deps": [{ "out_point": { .. }, "dep_type": "dep_group" }]
+//! }
+//! ```
+//!
+//! Out points, cell deps and outputs use the JSON-RPC encoding of CKB. The registry cell
+//! must be one of the cells, the contracts require it. Lock deps are optional, they are
+//! appended to the cell deps of every transaction so it can be signed as is.
+
+use crate::error::Error;
+use cdm_sdk::script_utils::registry::Registry;
+use cdm_sdk::{ContractRef, Deployment, Funding, LiveCell, RegistryCell};
+use ckb_jsonrpc_types as json_types;
+use ckb_types::{bytes::Bytes, core::ScriptHashType, packed::*, prelude::*, H256};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Binaries of the contracts as built by capsule, in the order of `Deployment`
+pub const CONTRACT_NAMES: [&str; 4] = ["issuer-type", "class-type", "nft-type", "metadata"];
+
+#[derive(Deserialize)]
+struct ContractJson {
+    code_hash: H256,
+    hash_type: json_types::ScriptHashType,
+    cell_dep: json_types::CellDep,
+}
+
+impl From<ContractJson> for ContractRef {
+    fn from(contract: ContractJson) -> Self {
+        ContractRef {
+            code_hash: contract.code_hash.0,
+            hash_type: ScriptHashType::from(contract.hash_type).into(),
+            cell_dep: contract.cell_dep.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct DeploymentJson {
+    issuer_type: ContractJson,
+    class_type: ContractJson,
+    nft_type: ContractJson,
+    metadata_type: ContractJson,
+    registry: json_types::CellDep,
+}
+
+#[derive(Deserialize)]
+struct CellJson {
+    out_point: json_types::OutPoint,
+    output: json_types::CellOutput,
+    data: json_types::JsonBytes,
+}
+
+#[derive(Deserialize)]
+struct SnapshotJson {
+    deployment: DeploymentJson,
+    cells: Vec<CellJson>,
+    #[serde(default)]
+    lock_deps: Vec<json_types::CellDep>,
+}
+
+pub struct Snapshot {
+    pub deployment: Deployment,
+    pub lock_deps: Vec<CellDep>,
+    cells: Vec<LiveCell>,
+}
+
+impl Snapshot {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let snapshot: SnapshotJson = serde_json::from_str(json)?;
+        let cells: Vec<LiveCell> = snapshot
+            .cells
+            .into_iter()
+            .map(|cell| LiveCell {
+                out_point: cell.out_point.into(),
+                output: cell.output.into(),
+                data: cell.data.into_bytes(),
+            })
+            .collect();
+        let deployment = snapshot.deployment;
+        let registry_dep: CellDep = deployment.registry.into();
+        let registry_data = &find_cell(&cells, &registry_dep.out_point())?.data;
+        let registry = Registry::from_data(registry_data)
+            .map_err(|err| Error::Snapshot(format!("registry cell data: {:?}", err)))?;
+        Ok(Snapshot {
+            deployment: Deployment {
+                issuer_type: deployment.issuer_type.into(),
+                class_type: deployment.class_type.into(),
+                nft_type: deployment.nft_type.into(),
+                metadata_type: deployment.metadata_type.into(),
+                registry: RegistryCell {
+                    cell_dep: registry_dep,
+                    registry,
+                },
+            },
+            lock_deps: snapshot.lock_deps.into_iter().map(Into::into).collect(),
+            cells,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn cell(&self, out_point: &OutPoint) -> Result<&LiveCell, Error> {
+        find_cell(&self.cells, out_point)
+    }
+
+    /// Adds the code cells of the contracts which are not in the snapshot, from the
+    /// binaries in dir. Contracts deployed in a dep group or referenced by the type hash of
+    /// their code cell must be in the snapshot.
+    pub fn load_contracts(&mut self, dir: &Path) -> Result<(), Error> {
+        let deployment = &self.deployment;
+        let contracts = [
+            &deployment.issuer_type,
+            &deployment.class_type,
+            &deployment.nft_type,
+            &deployment.metadata_type,
+        ];
+        let mut code_cells = Vec::new();
+        for (contract, name) in contracts.iter().zip(CONTRACT_NAMES.iter()) {
+            let out_point = contract.cell_dep.out_point();
+            if self.cell(&out_point).is_ok() {
+                continue;
+            }
+            // The type script of the code cell is not known from the binary
+            if contract.hash_type == ScriptHashType::Type as u8 {
+                return Err(Error::Snapshot(format!(
+                    "code cell {} of {} referenced by type not found",
+                    format_out_point(&out_point),
+                    name
+                )));
+            }
+            // Only the data of code cells is loaded by the scripts
+            code_cells.push(LiveCell {
+                out_point,
+                output: CellOutput::default(),
+                data: Bytes::from(fs::read(dir.join(name))?),
+            });
+        }
+        self.cells.extend(code_cells);
+        Ok(())
+    }
+
+    /// Funding of the transaction from the given cells, or from every cell without type
+    /// and data when none is given. The change goes back to the lock of the first one.
+    pub fn funding(&self, out_points: &[OutPoint], fee: u64) -> Result<Funding, Error> {
+        let cells: Vec<LiveCell> = if out_points.is_empty() {
+            self.cells
+                .iter()
+                .filter(|cell| cell.output.type_().is_none() && cell.data.is_empty())
+                .cloned()
+                .collect()
+        } else {
+            out_points
+                .iter()
+                .map(|out_point| self.cell(out_point).map(Clone::clone))
+                .collect::<Result<_, _>>()?
+        };
+        let change_lock = cells
+            .first()
+            .map(|cell| cell.output.lock())
+            .ok_or_else(|| Error::Snapshot("no funding cell".to_string()))?;
+        Ok(Funding {
+            cells,
+            change_lock,
+            fee,
+        })
+    }
+}
+
+fn find_cell<'a>(cells: &'a [LiveCell], out_point: &OutPoint) -> Result<&'a LiveCell, Error> {
+    cells
+        .iter()
+        .find(|cell| &cell.out_point == out_point)
+        .ok_or_else(|| Error::Snapshot(format!("cell {} not found", format_out_point(out_point))))
+}
+
+/// Out point as tx_hash:index, the format of the command line
+pub fn format_out_point(out_point: &OutPoint) -> String {
+    let index: u32 = out_point.index().unpack();
+    format!(
+        "0x{}:{}",
+        hex::encode(out_point.tx_hash().as_slice()),
+        index
+    )
+}
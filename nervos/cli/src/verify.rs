@@ -0,0 +1,120 @@
+//! Local verification of the type scripts of a composed transaction. Lock scripts are
+//! skipped, the transaction is not signed yet.
+
+use crate::error::Error;
+use crate::mock_tx::MockTx;
+use cdm_sdk::LiveCell;
+use ckb_chain_spec::consensus::ConsensusBuilder;
+use ckb_script::{ScriptError, ScriptGroupType, TransactionScriptsVerifier, TxVerifyEnv};
+use ckb_traits::{CellDataProvider, HeaderProvider};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMeta, CellMetaBuilder, ResolvedTransaction},
+        Cycle, HeaderBuilder, HeaderView,
+    },
+    packed::*,
+    prelude::*,
+};
+
+/// Cycles limit of a transaction
+pub const MAX_CYCLES: Cycle = 70_000_000;
+
+// Cells are resolved with their data, headers are not available
+struct DataLoader<'a>(&'a MockTx);
+
+impl<'a> DataLoader<'a> {
+    fn cell(&self, out_point: &OutPoint) -> Option<&LiveCell> {
+        let mock_tx = self.0;
+        mock_tx
+            .inputs
+            .iter()
+            .chain(mock_tx.cell_deps.iter().map(|(_, cell)| cell))
+            .chain(mock_tx.dep_groups.iter())
+            .find(|cell| &cell.out_point == out_point)
+    }
+}
+
+impl<'a> CellDataProvider for DataLoader<'a> {
+    fn get_cell_data(&self, out_point: &OutPoint) -> Option<Bytes> {
+        self.cell(out_point).map(|cell| cell.data.clone())
+    }
+
+    fn get_cell_data_hash(&self, out_point: &OutPoint) -> Option<Byte32> {
+        self.cell(out_point)
+            .map(|cell| CellOutput::calc_data_hash(&cell.data))
+    }
+}
+
+impl<'a> HeaderProvider for DataLoader<'a> {
+    fn get_header(&self, _hash: &Byte32) -> Option<HeaderView> {
+        None
+    }
+}
+
+fn cell_meta(cell: &LiveCell) -> CellMeta {
+    CellMetaBuilder::from_cell_output(cell.output.clone(), cell.data.clone())
+        .out_point(cell.out_point.clone())
+        .build()
+}
+
+/// Runs every type script group and returns the cycles of each, by type script
+pub fn verify(mock_tx: &MockTx, max_cycles: Cycle) -> Result<Vec<(Script, Cycle)>, Error> {
+    let resolved_tx = ResolvedTransaction {
+        transaction: mock_tx.tx.clone(),
+        resolved_cell_deps: mock_tx
+            .cell_deps
+            .iter()
+            .map(|(_, cell)| cell_meta(cell))
+            .collect(),
+        resolved_inputs: mock_tx.inputs.iter().map(cell_meta).collect(),
+        resolved_dep_groups: mock_tx.dep_groups.iter().map(cell_meta).collect(),
+    };
+    let data_loader = DataLoader(mock_tx);
+    let consensus = ConsensusBuilder::default().build();
+    let tip = HeaderBuilder::default().build();
+    let tx_env = TxVerifyEnv::new_submit(&tip);
+    let verifier = TransactionScriptsVerifier::new(&resolved_tx, &consensus, &data_loader, &tx_env);
+
+    // Groups in the order of the VM, inputs first
+    let mut type_scripts: Vec<Script> = Vec::new();
+    let input_types = mock_tx
+        .inputs
+        .iter()
+        .map(|cell| cell.output.type_().to_opt());
+    let output_types = mock_tx
+        .tx
+        .outputs()
+        .into_iter()
+        .map(|output| output.type_().to_opt());
+    for type_script in input_types.chain(output_types).flatten() {
+        if type_scripts
+            .iter()
+            .all(|script| script.as_slice() != type_script.as_slice())
+        {
+            type_scripts.push(type_script);
+        }
+    }
+
+    let mut cycles = Vec::new();
+    let mut remaining_cycles = max_cycles;
+    for type_script in type_scripts {
+        let script_hash = type_script.calc_script_hash();
+        let group_cycles = verifier
+            .verify_single(ScriptGroupType::Type, &script_hash, remaining_cycles)
+            .map_err(|err| {
+                let code = match err {
+                    ScriptError::ValidationFailure(code) => Some(code),
+                    _ => None,
+                };
+                Error::Verification {
+                    script: type_script.clone(),
+                    code,
+                    message: err.to_string(),
+                }
+            })?;
+        remaining_cycles -= group_cycles;
+        cycles.push((type_script, group_cycles));
+    }
+    Ok(cycles)
+}
@@ -2,17 +2,22 @@ use alloc::vec::Vec;
 use ckb_std::{
   ckb_constants::Source,
   ckb_types::{bytes::Bytes, packed::*, prelude::*},
-  high_level::{load_cell_data, load_script},
+  high_level::{load_cell_type, load_cell_type_hash, load_script, QueryIter},
 };
 use core::result::Result;
 use script_utils::{
-  class::{Class, CLASS_TYPE_ARGS_LEN},
+  action::{check_action_transition, load_declared_action, CdmAction},
+  auth::{check_owner_auth, owner_checks, OwnerCheck},
+  class::{ClassReader, CLASS_TYPE_ARGS_LEN},
   error::Error,
   helper::{
-    check_group_input_witness_is_none_with_type, count_cells_by_type, count_cells_by_type_hash,
-    load_cell_data_by_type_hash, load_output_type_args_ids, Action,
+    count_cells_by_type_hash, inputs_have_type, is_same_code, load_cell_data_by_type_hash,
+    load_output_type_args_ids, parse_group_action, Action,
   },
   issuer::{Issuer, ISSUER_TYPE_ARGS_LEN},
+  registry::load_registry,
+  type_id::blake2b_256,
+  rejected, trace,
 };
 
 fn check_issuer_id<'a>(class_args: &'a Bytes) -> impl Fn(&[u8]) -> bool + 'a {
@@ -23,58 +28,60 @@ fn check_class_type<'a>(class_type: &'a Script) -> impl Fn(&Script) -> bool + 'a
   let class_args: Bytes = class_type.args().unpack();
   move |type_: &Script| {
     let type_args: Bytes = type_.args().unpack();
-    type_.code_hash().as_slice() == class_type.code_hash().as_slice()
-      && type_.hash_type().as_slice() == class_type.hash_type().as_slice()
+    is_same_code(type_, class_type)
       && type_args.len() == CLASS_TYPE_ARGS_LEN
       && type_args[0..ISSUER_TYPE_ARGS_LEN] == class_args[0..ISSUER_TYPE_ARGS_LEN]
   }
 }
 
-fn load_class_data(source: Source) -> Result<Vec<u8>, Error> {
-  load_cell_data(0, source).map_err(|_| Error::ClassDataInvalid)
-}
-
-fn parse_class_action(class_type: &Script) -> Result<Action, Error> {
-  let class_inputs_count = count_cells_by_type(Source::Input, &check_class_type(class_type));
-  if class_inputs_count == 0 {
-    return Ok(Action::Create);
-  }
-  let class_outputs_count = count_cells_by_type(Source::Output, &check_class_type(class_type));
-  if class_inputs_count == 1 && class_outputs_count == 0 {
-    return Ok(Action::Destroy);
-  }
-  if class_inputs_count == 1 && class_outputs_count == 1 {
-    return Ok(Action::Update);
-  }
-  Err(Error::ClassCellsCountError)
+// The issuer of the class can act for its owner
+fn check_class_owner_auth(class_type: &Script, input_class: &ClassReader) -> Result<(), Error> {
+  let class_args: Bytes = class_type.args().unpack();
+  let is_issuer = check_issuer_id(&class_args);
+  let is_issuer_type = |type_: &Script| is_issuer(&blake2b_256(type_.as_slice()));
+  let mut checks = owner_checks(input_class.layout.owner_lock_hash);
+  checks.push(OwnerCheck::Delegate(&is_issuer_type));
+  check_owner_auth(&checks)
 }
 
 fn handle_creation(class_type: &Script) -> Result<(), Error> {
-
   let class_args: Bytes = class_type.args().unpack();
   let issuer_inputs_count = count_cells_by_type_hash(Source::Input, &check_issuer_id(&class_args));
   if issuer_inputs_count != 1 {
-    return Err(Error::IssuerCellsCountError);
+    return Err(rejected!(Error::IssuerCellsCountError));
+  }
+
+  // The issuer cell must run the registered issuer type
+  let issuer_type = load_registry()?.issuer_type;
+  let is_issuer = check_issuer_id(&class_args);
+  let issuer_index = QueryIter::new(load_cell_type_hash, Source::Input)
+    .position(|type_hash_opt| type_hash_opt.map_or(false, |type_hash| is_issuer(&type_hash)))
+    .ok_or_else(|| rejected!(Error::IssuerCellsCountError))?;
+  let issuer_cell_type = load_cell_type(issuer_index, Source::Input)?;
+  if !issuer_cell_type.map_or(false, |type_| issuer_type.matches(&type_)) {
+    return Err(rejected!(Error::IssuerTypeInvalid));
   }
 
   let load_issuer =
     |source| match load_cell_data_by_type_hash(source, &check_issuer_id(&class_args)) {
       Some(data) => Ok(Issuer::from_data(&data)?),
-      None => Err(Error::IssuerDataInvalid),
+      None => Err(rejected!(Error::IssuerDataInvalid)),
     };
   let input_issuer = load_issuer(Source::Input)?;
   let output_issuer = load_issuer(Source::Output)?;
 
   if output_issuer.class_count <= input_issuer.class_count {
-    return Err(Error::IssuerClassCountError);
+    return Err(rejected!(Error::IssuerClassCountError));
   }
 
-  let outputs_class_ids =
-    load_output_type_args_ids(ISSUER_TYPE_ARGS_LEN, &check_class_type(&class_type));
+  // Only classes without a paired input are created, the others are updated
+  let is_issuer_class = check_class_type(class_type);
+  let is_created_class = |type_: &Script| is_issuer_class(type_) && !inputs_have_type(type_);
+  let outputs_class_ids = load_output_type_args_ids(ISSUER_TYPE_ARGS_LEN, &is_created_class);
   let class_outputs_increased_count =
     (output_issuer.class_count - input_issuer.class_count) as usize;
   if class_outputs_increased_count != outputs_class_ids.len() {
-    return Err(Error::ClassCellsCountError);
+    return Err(rejected!(Error::ClassCellsCountError));
   }
 
   let mut issuer_cell_class_ids = Vec::new();
@@ -83,43 +90,53 @@ fn handle_creation(class_type: &Script) -> Result<(), Error> {
   }
 
   if outputs_class_ids != issuer_cell_class_ids {
-    return Err(Error::ClassIdIncreaseError);
+    return Err(rejected!(Error::ClassIdIncreaseError));
   }
   Ok(())
 }
 
 fn handle_update(class_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(class_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
-  }
-  let load_class = |source| Class::from_data(&load_class_data(source)?[..]);
-
-  let input_class = load_class(Source::GroupInput)?;
-  let output_class = load_class(Source::GroupOutput)?;
+  // Names and descriptions are compared in place instead of being copied
+  let input_class = ClassReader::load(0, Source::GroupInput)?;
+  check_class_owner_auth(class_type, &input_class)?;
+  let output_class = ClassReader::load(0, Source::GroupOutput)?;
 
-  if !input_class.immutable_equal(&output_class) {
-    return Err(Error::ClassImmutableFieldsNotSame);
+  if !input_class.immutable_equal(&output_class)? {
+    return Err(rejected!(Error::ClassImmutableFieldsNotSame));
   }
   Ok(())
 }
 
 fn handle_destroying(class_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(class_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
+  let input_class = ClassReader::load(0, Source::GroupInput)?;
+  check_class_owner_auth(class_type, &input_class)
+}
+
+fn check_declared_action(action: &Action) -> Result<(), Error> {
+  let declared_action = match load_declared_action(action)? {
+    Some(declared_action) => declared_action,
+    None => return Ok(()),
+  };
+  trace!("action_declared", action = declared_action);
+  match declared_action {
+    CdmAction::Mint { .. } | CdmAction::Transfer | CdmAction::Update | CdmAction::Burn => {
+      check_action_transition(action, &declared_action)
+    }
+    _ => Err(rejected!(Error::ActionNotMatchTransition)),
   }
-  Ok(())
 }
 
 pub fn main() -> Result<(), Error> {
   let class_type = load_script()?;
   let class_args: Bytes = class_type.args().unpack();
   if class_args.len() != CLASS_TYPE_ARGS_LEN {
-    return Err(Error::TypeArgsInvalid);
+    return Err(rejected!(Error::TypeArgsInvalid));
   }
 
-  match parse_class_action(&class_type)? {
+  let action = parse_group_action(Error::ClassCellsCountError)?;
+  trace!("action_detected", action = action);
+  check_declared_action(&action)?;
+  match action {
     Action::Create => handle_creation(&class_type),
     Action::Update => handle_update(&class_type),
     Action::Destroy => handle_destroying(&class_type),
@@ -4,8 +4,9 @@
 //! See `entry.rs` for the `main` function. 
 //! See `error.rs` for the `Error` type.
 
-#![no_std]
-#![no_main]
+// The simulator feature builds a native executable for debugging and coverage
+#![cfg_attr(not(feature = "simulator"), no_std)]
+#![cfg_attr(not(feature = "simulator"), no_main)]
 #![feature(asm)]
 #![feature(lang_items)]
 #![feature(alloc_error_handler)]
@@ -16,13 +17,26 @@ mod entry;
 
 pub use script_utils::error;
 
+#[cfg(not(feature = "simulator"))]
 use ckb_std::{
     default_alloc,
 };
 
+#[cfg(not(feature = "simulator"))]
 ckb_std::entry!(program_entry);
+#[cfg(not(feature = "simulator"))]
 default_alloc!();
 
+#[cfg(feature = "simulator")]
+extern crate alloc;
+
+/// Native entry, the syscalls are served by ckb-x64-simulator from the transaction
+/// in `CKB_TX_FILE`
+#[cfg(feature = "simulator")]
+fn main() {
+    std::process::exit(program_entry(0, core::ptr::null()) as i32);
+}
+
 /// program entry
 ///
 ///  Both `argc` and `argv` can be omitted.
@@ -30,6 +44,10 @@ fn program_entry(_argc: u64, _argv: *const *const u8) -> i8 {
     // Call main function and return error code
     match entry::main() {
         Ok(_) => 0,
-        Err(err) => err as i8,
+        Err(err) => {
+            let code = error::Contract::ClassType.exit_code(err);
+            script_utils::trace!("validation_failed", error = err, code = code);
+            code
+        }
     }
 }
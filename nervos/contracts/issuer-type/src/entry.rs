@@ -1,92 +1,84 @@
 use alloc::vec::Vec;
-use blake2b_rs::Blake2bBuilder;
 use ckb_std::{
   ckb_constants::Source,
   ckb_types::{bytes::Bytes, packed::*, prelude::*},
-  high_level::{load_cell_data, load_input, load_script},
+  high_level::{load_cell_data, load_script},
 };
 use core::result::Result;
 use script_utils::{
+  action::{check_action_transition, load_declared_action, CdmAction},
+  auth::{check_owner_auth, owner_checks},
   error::Error,
-  helper::{check_group_input_witness_is_none_with_type, count_cells_by_type, Action},
+  helper::{parse_group_action, Action},
   issuer::{Issuer, ISSUER_TYPE_ARGS_LEN},
+  type_id::check_type_id,
+  rejected, trace,
 };
 
 fn load_issuer_data(source: Source) -> Result<Vec<u8>, Error> {
-  load_cell_data(0, source).map_err(|_| Error::IssuerDataInvalid)
-}
-
-fn parse_issuer_action(issuer_type: &Script) -> Result<Action, Error> {
-  let count_cells = |source| {
-    count_cells_by_type(source, &|type_: &Script| {
-      type_.as_slice() == issuer_type.as_slice()
-    })
-  };
-  let issuer_cells_count = (count_cells(Source::Input), count_cells(Source::Output));
-  match issuer_cells_count {
-    (0, 1) => Ok(Action::Create),
-    (1, 1) => Ok(Action::Update),
-    (1, 0) => Ok(Action::Destroy),
-    _ => Err(Error::IssuerCellsCountError),
-  }
+  load_cell_data(0, source).map_err(|_| rejected!(Error::IssuerDataInvalid))
 }
 
 fn handle_creation(issuer_type: &Script) -> Result<(), Error> {
-  let first_input = load_input(0, Source::Input)?;
-  let first_input_previous_output = first_input.previous_output();
-  let mut blake2b = Blake2bBuilder::new(32).build();
-  blake2b.update(first_input_previous_output.tx_hash().as_slice());
-  blake2b.update(first_input_previous_output.index().as_slice());
-  let mut ret = [0; 32];
-  blake2b.finalize(&mut ret);
-
   let issuer_args: Bytes = issuer_type.args().unpack();
-  if issuer_args[..] != ret[0..ISSUER_TYPE_ARGS_LEN] {
-    return Err(Error::TypeArgsInvalid);
-  }
+  check_type_id(issuer_type, &issuer_args[..])?;
+
   let issuer = Issuer::from_data(&load_issuer_data(Source::GroupOutput)?[..])?;
   if issuer.class_count != 0 {
-    return Err(Error::IssuerClassCountError);
+    return Err(rejected!(Error::IssuerClassCountError));
   }
   Ok(())
 }
 
-fn handle_update(issuer_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(issuer_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
-  }
+fn handle_update() -> Result<(), Error> {
   let load_issuer = |source| Issuer::from_data(&load_issuer_data(source)?[..]);
   let input_issuer = load_issuer(Source::GroupInput)?;
+  // Only the owner can spend the cell, whatever lock it uses
+  check_owner_auth(&owner_checks(input_issuer.owner_lock_hash))?;
   let output_issuer = load_issuer(Source::GroupOutput)?;
   if output_issuer.class_count < input_issuer.class_count {
-    return Err(Error::IssuerClassCountError);
+    return Err(rejected!(Error::IssuerClassCountError));
   }
   Ok(())
 }
 
-fn handle_destroying(issuer_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(issuer_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
-  }
+fn handle_destroying() -> Result<(), Error> {
   let input_issuer = Issuer::from_data(&load_issuer_data(Source::GroupInput)?[..])?;
+  // Only the owner can spend the cell, whatever lock it uses
+  check_owner_auth(&owner_checks(input_issuer.owner_lock_hash))?;
   if input_issuer.class_count != 0 {
-    return Err(Error::IssuerCellCannotDestroyed);
+    return Err(rejected!(Error::IssuerCellCannotDestroyed));
   }
   Ok(())
 }
 
+fn check_declared_action(action: &Action) -> Result<(), Error> {
+  let declared_action = match load_declared_action(action)? {
+    Some(declared_action) => declared_action,
+    None => return Ok(()),
+  };
+  trace!("action_declared", action = declared_action);
+  match declared_action {
+    CdmAction::Mint { .. } | CdmAction::Transfer | CdmAction::Update | CdmAction::Burn => {
+      check_action_transition(action, &declared_action)
+    }
+    _ => Err(rejected!(Error::ActionNotMatchTransition)),
+  }
+}
+
 pub fn main() -> Result<(), Error> {
   let issuer_type = load_script()?;
   let issuer_args: Bytes = issuer_type.args().unpack();
   if issuer_args.len() != ISSUER_TYPE_ARGS_LEN {
-    return Err(Error::TypeArgsInvalid);
+    return Err(rejected!(Error::TypeArgsInvalid));
   }
 
-  match parse_issuer_action(&issuer_type)? {
+  let action = parse_group_action(Error::IssuerCellsCountError)?;
+  trace!("action_detected", action = action);
+  check_declared_action(&action)?;
+  match action {
     Action::Create => handle_creation(&issuer_type),
-    Action::Update => handle_update(&issuer_type),
-    Action::Destroy => handle_destroying(&issuer_type),
+    Action::Update => handle_update(),
+    Action::Destroy => handle_destroying(),
   }
 }
@@ -1,86 +1,209 @@
 use alloc::vec::Vec;
-use blake2b_rs::Blake2bBuilder;
 use ckb_std::{
   ckb_constants::Source,
   ckb_types::{bytes::Bytes, packed::*, prelude::*},
-  high_level::{load_cell_data, load_input, load_script},
+  high_level::{load_cell_data, load_script},
 };
 use core::result::Result;
 use script_utils::{
+  action::{check_action_transition, load_declared_action, CdmAction},
+  attribute::parse_attributes,
+  auth::{check_owner_auth, owner_checks},
   error::Error,
-  helper::{check_group_input_witness_is_none_with_type, count_cells_by_type, Action},
-  metadata::{Metadata, METADATA_TYPE_ARGS_LEN},
+  helper::{
+    is_same_code, load_cells_data_by_type, parse_group_action, Action,
+  },
+  metadata::{
+    Metadata, MetadataChunk, MetadataReader, METADATA_FLAG_ATTRIBUTES, METADATA_TYPE_ARGS_LEN,
+    PREV_DATA_HASH_LEN,
+  },
+  type_id::check_type_id,
+  rejected, trace,
 };
 
+fn check_metadata_type<'a>(metadata_type: &'a Script) -> impl Fn(&Script) -> bool + 'a {
+  move |type_: &Script| {
+    let type_args: Bytes = type_.args().unpack();
+    is_same_code(type_, metadata_type)
+      && type_args.len() == METADATA_TYPE_ARGS_LEN
+  }
+}
+
 fn load_metadata_data(source: Source) -> Result<Vec<u8>, Error> {
-  load_cell_data(0, source).map_err(|_| Error::MetadataDataInvalid)
+  load_cell_data(0, source).map_err(|_| rejected!(Error::MetadataDataInvalid))
 }
 
-fn parse_metadata_action(metadata_type: &Script) -> Result<Action, Error> {
-  let count_cells = |source| {
-    count_cells_by_type(source, &|type_: &Script| {
-      type_.as_slice() == metadata_type.as_slice()
-    })
-  };
-  let metadata_cells_count = (count_cells(Source::Input), count_cells(Source::Output));
-  match metadata_cells_count {
-    (0, 1) => Ok(Action::Create),
-    (1, 1) => Ok(Action::Update),
-    (1, 0) => Ok(Action::Destroy),
-    _ => Err(Error::MetadataCellsCountError),
+fn handle_creation(metadata_type: &Script) -> Result<(), Error> {
+  let metadata_args: Bytes = metadata_type.args().unpack();
+  check_type_id(metadata_type, &metadata_args[..])?;
+
+  let metadata = Metadata::from_data(&load_metadata_data(Source::GroupOutput)?[..])?;
+  if let Some(history) = &metadata.history {
+    if history.revision != 0 || history.prev_data_hash != [0u8; PREV_DATA_HASH_LEN] {
+      return Err(rejected!(Error::MetadataHistoryInvalid));
+    }
+  }
+  validate_attributes(metadata.flags, metadata.data_content())?;
+  match &metadata.chunk {
+    Some(chunk) => validate_chunk(metadata_type, chunk),
+    None => Ok(()),
   }
 }
 
-fn handle_creation(metadata_type: &Script) -> Result<(), Error> {
-  let first_input = load_input(0, Source::Input)?;
-  let first_input_previous_output = first_input.previous_output();
-  let mut blake2b = Blake2bBuilder::new(32).build();
-  blake2b.update(first_input_previous_output.tx_hash().as_slice());
-  blake2b.update(first_input_previous_output.index().as_slice());
-  let mut ret = [0; 32];
-  blake2b.finalize(&mut ret);
+fn validate_attributes(flags: u8, data_content: &[u8]) -> Result<(), Error> {
+  if flags & METADATA_FLAG_ATTRIBUTES == METADATA_FLAG_ATTRIBUTES {
+    parse_attributes(data_content).map_err(|_| rejected!(Error::MetadataAttributesInvalid))?;
+  }
+  Ok(())
+}
 
+fn load_document_chunks(
+  metadata_type: &Script,
+  source: Source,
+  document_id: &[u8],
+) -> Result<Vec<MetadataChunk>, Error> {
+  let mut chunks = Vec::new();
+  for data in load_cells_data_by_type(source, &check_metadata_type(metadata_type)) {
+    let metadata = match Metadata::from_data(&data[..]) {
+      Ok(metadata) => metadata,
+      // Invalid metadata outputs are rejected by their own scripts
+      Err(err) if matches!(source, Source::Output) => return Err(err),
+      Err(_) => continue,
+    };
+    if let Some(chunk) = metadata.chunk {
+      if chunk.document_id[..] == document_id[..] {
+        chunks.push(chunk);
+      }
+    }
+  }
+  Ok(chunks)
+}
+
+fn validate_chunk(metadata_type: &Script, chunk: &MetadataChunk) -> Result<(), Error> {
+  // The first chunk is the head of the document and its type args are the document id
   let metadata_args: Bytes = metadata_type.args().unpack();
-  if metadata_args[..] != ret[0..METADATA_TYPE_ARGS_LEN] {
-    return Err(Error::TypeArgsInvalid);
+  if (chunk.index == 0) != (metadata_args[..] == chunk.document_id[..]) {
+    return Err(rejected!(Error::MetadataChunkInvalid));
+  }
+
+  let output_chunks = load_document_chunks(metadata_type, Source::Output, &chunk.document_id)?;
+  if output_chunks.iter().any(|output_chunk| output_chunk.count != chunk.count) {
+    return Err(rejected!(Error::MetadataChunkCountError));
+  }
+  let ordered = output_chunks
+    .windows(2)
+    .all(|pair| pair[0].index < pair[1].index);
+  if !ordered {
+    return Err(rejected!(Error::MetadataChunkOrderError));
+  }
+
+  // The head chunk checks that the whole document is available in outputs or cell deps
+  if chunk.index == 0 {
+    let dep_chunks = load_document_chunks(metadata_type, Source::CellDep, &chunk.document_id)?;
+    let mut present = Vec::new();
+    present.resize(chunk.count as usize, false);
+    for document_chunk in output_chunks.iter().chain(dep_chunks.iter()) {
+      if document_chunk.count == chunk.count {
+        present[document_chunk.index as usize] = true;
+      }
+    }
+    if present.iter().any(|chunk_present| !chunk_present) {
+      return Err(rejected!(Error::MetadataChunkCountError));
+    }
   }
-  let _metadata = Metadata::from_data(&load_metadata_data(Source::GroupOutput)?[..])?;
-  
   Ok(())
 }
 
-fn handle_update(metadata_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(metadata_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
+fn validate_history(input: &MetadataReader, output: &MetadataReader) -> Result<(), Error> {
+  let input_history = input.layout.history.as_ref();
+  let output_history = match &output.layout.history {
+    Some(history) => history,
+    // History mode cannot be turned off once it is enabled
+    None if input_history.is_some() => return Err(rejected!(Error::MetadataHistoryInvalid)),
+    None => return Ok(()),
+  };
+  // Nothing to record when data and history are carried over unchanged
+  if input_history == Some(output_history) && input.data_equal(output)? {
+    return Ok(());
   }
-  let load_metadata = |source| Metadata::from_data(&load_metadata_data(source)?[..]);
-  let input_metadata = load_metadata(Source::GroupInput)?;
-  let output_metadata = load_metadata(Source::GroupOutput)?;
-  if !input_metadata.immutable_equal(&output_metadata) {
-    return Err(Error::MetadataImmutableFieldsNotSame);
+
+  let input_revision = input_history.map_or(0, |history| history.revision);
+  if input_revision.checked_add(1) != Some(output_history.revision) {
+    return Err(rejected!(Error::MetadataRevisionIncreaseError));
+  }
+  // The input data is hashed in place, it can be much larger than the fields we parse
+  if output_history.prev_data_hash != input.data_hash()? {
+    return Err(rejected!(Error::MetadataPrevHashNotSame));
   }
   Ok(())
 }
 
-fn handle_destroying(metadata_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(metadata_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
+fn handle_update(metadata_type: &Script) -> Result<(), Error> {
+  let input = MetadataReader::load(0, Source::GroupInput)?;
+  // Only the owner can spend the cell, whatever lock it uses
+  check_owner_auth(&owner_checks(input.layout.owner_lock_hash))?;
+  // Both cells are compared in place, only the data of attributes is loaded
+  let output = MetadataReader::load(0, Source::GroupOutput)?;
+  if input.is_frozen() {
+    if !input.cell_data_equal(&output)? {
+      return Err(rejected!(Error::MetadataFrozenCannotModify));
+    }
+    return Ok(());
+  }
+  if !input.immutable_equal(&output)? {
+    return Err(rejected!(Error::MetadataImmutableFieldsNotSame));
+  }
+  validate_history(&input, &output)?;
+  if output.layout.flags & METADATA_FLAG_ATTRIBUTES == METADATA_FLAG_ATTRIBUTES {
+    validate_attributes(output.layout.flags, &output.load_data_content()?)?;
+  }
+  match &output.layout.chunk {
+    Some(chunk) => validate_chunk(metadata_type, chunk),
+    None => Ok(()),
+  }
+}
+
+fn handle_destroying() -> Result<(), Error> {
+  let input = MetadataReader::load(0, Source::GroupInput)?;
+  // Only the owner can spend the cell, whatever lock it uses
+  check_owner_auth(&owner_checks(input.layout.owner_lock_hash))?;
+  if input.is_frozen() {
+    return Err(rejected!(Error::MetadataFrozenCannotDestroy));
   }
   Ok(())
 }
 
+fn check_declared_action(action: &Action) -> Result<(), Error> {
+  let declared_action = match load_declared_action(action)? {
+    Some(declared_action) => declared_action,
+    None => return Ok(()),
+  };
+  trace!("action_declared", action = declared_action);
+  match &declared_action {
+    CdmAction::UpdateMetadata { prev_data_hash } => {
+      if prev_data_hash[..] != MetadataReader::load(0, Source::GroupInput)?.data_hash()?[..] {
+        return Err(rejected!(Error::ActionPrevDataHashNotSame));
+      }
+    }
+    CdmAction::Mint { .. } | CdmAction::Transfer | CdmAction::Burn => {}
+    _ => return Err(rejected!(Error::ActionNotMatchTransition)),
+  }
+  check_action_transition(action, &declared_action)
+}
+
 pub fn main() -> Result<(), Error> {
   let metadata_type = load_script()?;
   let metadata_args: Bytes = metadata_type.args().unpack();
   if metadata_args.len() != METADATA_TYPE_ARGS_LEN {
-    return Err(Error::TypeArgsInvalid);
+    return Err(rejected!(Error::TypeArgsInvalid));
   }
 
-  match parse_metadata_action(&metadata_type)? {
+  let action = parse_group_action(Error::MetadataCellsCountError)?;
+  trace!("action_detected", action = action);
+  check_declared_action(&action)?;
+  match action {
     Action::Create => handle_creation(&metadata_type),
     Action::Update => handle_update(&metadata_type),
-    Action::Destroy => handle_destroying(&metadata_type),
+    Action::Destroy => handle_destroying(),
   }
 }
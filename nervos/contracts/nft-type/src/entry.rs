@@ -1,174 +1,218 @@
 mod validator;
 use self::validator::validate_immutable_nft_fields;
 use alloc::vec::Vec;
-use blake2b_rs::Blake2bBuilder;
 use ckb_std::{
   ckb_constants::Source,
   ckb_types::{bytes::Bytes, packed::*, prelude::*},
-  high_level::{load_cell_data, load_script, QueryIter, load_cell_type, load_input, load_cell_occupied_capacity},
+  high_level::{load_cell_data, load_script},
 };
 use core::result::Result;
 use script_utils::{
+  action::{check_action_transition, load_declared_action, CdmAction},
+  attribute::{parse_attributes, AttributeSchema},
+  auth::{check_owner_auth, OwnerCheck},
   class::{Class, CLASS_TYPE_ARGS_LEN},
   issuer::ISSUER_TYPE_ARGS_LEN,
   error::Error,
   helper::{
-    check_group_input_witness_is_none_with_type, count_cells_by_type, load_cell_data_by_type,
-    load_class_type, Action,
-    load_payment_cell_capacity,
+    is_same_code, load_cell_data_by_type, load_cell_data_by_type_hash, load_cells_data_by_type,
+    parse_group_action, Action,
   },
+  metadata::Metadata,
   nft::{Nft, NFT_TYPE_ARGS_LEN},
+  registry::{load_registry, Registry},
+  tx_index::{IndexedCell, TxIndex},
+  type_id::check_type_id,
+  rejected, trace,
 };
 
-fn parse_type_opt(type_opt: &Option<Script>, predicate: &dyn Fn(&Script) -> bool) -> bool {
-  match type_opt {
-    Some(type_) => predicate(type_),
-    None => false,
+const SHANNONS_PER_CKB: u64 = 100_000_000;
+
+fn check_class_type<'a>(registry: &'a Registry, nft_args: &'a Bytes) -> impl Fn(&Script) -> bool + 'a {
+  move |type_: &Script| {
+    let type_args: Bytes = type_.args().unpack();
+    registry.class_type.matches(type_) && type_args[..] == nft_args[0..CLASS_TYPE_ARGS_LEN]
   }
 }
 
-fn check_class_type<'a>(nft_args: &'a Bytes) -> impl Fn(&Script) -> bool + 'a {
-  let class_type = load_class_type(nft_args);
-  move |type_: &Script| type_.as_slice() == class_type.as_slice()
+fn delegates_to_owner(class_data: &[u8]) -> bool {
+  Class::from_data(class_data).map_or(false, |class| class.delegates_to_owner())
 }
 
-fn check_nft_type<'a>(nft_type: &'a Script) -> impl Fn(&Script) -> bool + 'a {
+// The owner of the class of the nft can act for the holder only if the class was created
+// with the owner delegate flag, otherwise any class owner could move the NFTs they sold.
+// Class deps are matched by their args first, the registry is only loaded to confirm the
+// type of a class dep with the flag.
+fn check_nft_owner_auth(nft_type: &Script) -> Result<(), Error> {
   let nft_args: Bytes = nft_type.args().unpack();
-  move |type_: &Script| {
+  let has_class_args = |type_: &Script| {
     let type_args: Bytes = type_.args().unpack();
-    type_.code_hash().as_slice() == nft_type.code_hash().as_slice()
-      && type_.hash_type().as_slice() == nft_type.hash_type().as_slice()
-      && type_args.len() == NFT_TYPE_ARGS_LEN
-      && type_args[0..CLASS_TYPE_ARGS_LEN] == nft_args[0..CLASS_TYPE_ARGS_LEN]
+    type_args[..] == nft_args[0..CLASS_TYPE_ARGS_LEN]
+  };
+  let may_delegate = load_cells_data_by_type(Source::CellDep, &has_class_args)
+    .iter()
+    .any(|class_data| delegates_to_owner(class_data));
+  if !may_delegate {
+    return check_owner_auth(&[OwnerCheck::WitnessSignature]);
   }
-}
-
-fn get_cell_output_index_by_type(nft_type: &Script) -> Result<usize, Error> {
-  QueryIter::new(load_cell_type, Source::Output)
-    .position(|type_opt| {
-      match type_opt {
-        Some(type_) => {
-          type_.code_hash().as_slice() == nft_type.code_hash().as_slice()
-          && type_.hash_type().as_slice() == nft_type.hash_type().as_slice()
-          && type_.args().as_slice() == nft_type.args().as_slice()
-        },
-        None => false,
-      }
-    })
-    .map_or(Err(Error::Encoding), |index| Ok(index))
-}
 
-fn get_cell_occupied_capacity_by_type(nft_type: &Script) -> u64 {
-  let index = QueryIter::new(load_cell_type, Source::Output)
-    .position(|type_opt| {
-      match type_opt {
-        Some(type_) => {
-          type_.code_hash().as_slice() == nft_type.code_hash().as_slice()
-          && type_.hash_type().as_slice() == nft_type.hash_type().as_slice()
-          && type_.args().as_slice() == nft_type.args().as_slice()
-        },
-        None => false,
-      }
-    }).unwrap();
-  load_cell_occupied_capacity(index, Source::Output).unwrap()
+  let registry = load_registry()?;
+  let is_class_type = check_class_type(&registry, &nft_args);
+  let class_delegates = load_cell_data_by_type(Source::CellDep, &is_class_type)
+    .map_or(false, |class_data| delegates_to_owner(&class_data));
+  if class_delegates {
+    check_owner_auth(&[OwnerCheck::WitnessSignature, OwnerCheck::Delegate(&is_class_type)])
+  } else {
+    check_owner_auth(&[OwnerCheck::WitnessSignature])
+  }
 }
 
 fn check_issuer_type<'a>(nft_type: &'a Script) -> impl Fn(&Script) -> bool + 'a {
   let nft_args: Bytes = nft_type.args().unpack();
   move |type_: &Script| {
     let type_args: Bytes = type_.args().unpack();
-    type_.code_hash().as_slice() == nft_type.code_hash().as_slice()
-      && type_.hash_type().as_slice() == nft_type.hash_type().as_slice()
+    is_same_code(type_, nft_type)
       && type_args.len() == NFT_TYPE_ARGS_LEN
       && type_args[0..ISSUER_TYPE_ARGS_LEN] == nft_args[0..ISSUER_TYPE_ARGS_LEN]
   }
 }
 
 fn load_nft_data(source: Source) -> Result<Vec<u8>, Error> {
-  load_cell_data(0, source).map_err(|_| Error::NFTDataInvalid)
+  load_cell_data(0, source).map_err(|_| rejected!(Error::NFTDataInvalid))
 }
 
-fn parse_nft_action(nft_type: &Script) -> Result<Action, Error> {
-  let nft_inputs_count = count_cells_by_type(Source::Input, &check_nft_type(nft_type));
-  if nft_inputs_count == 0 {
-    return Ok(Action::Create);
+// Checks the metadata requirements of the class once and returns the attribute schema
+// its NFTs must satisfy
+fn load_class_schema(class: &Class) -> Result<Option<AttributeSchema>, Error> {
+  let metadata_type_hash = match class.metadata_type_hash() {
+    Some(type_hash) => type_hash,
+    None if class.requires_frozen_metadata() => return Err(rejected!(Error::NFTMetadataNotFrozen)),
+    None => return Ok(None),
+  };
+  let metadata_data =
+    load_cell_data_by_type_hash(Source::CellDep, &|type_hash: &[u8]| type_hash == metadata_type_hash)
+      .ok_or_else(|| rejected!(Error::NFTMetadataCellMissing))?;
+  let metadata = Metadata::from_data(&metadata_data)?;
+  if class.requires_frozen_metadata() && !metadata.is_frozen() {
+    return Err(rejected!(Error::NFTMetadataNotFrozen));
   }
+  Ok(metadata.schema)
+}
 
-  let nft_outputs_count = count_cells_by_type(Source::Output, &check_nft_type(nft_type));
-  if nft_inputs_count == 1 && nft_outputs_count == 0 {
-    return Ok(Action::Destroy);
+fn validate_nft_attributes(schema: &AttributeSchema, nft_cell: &IndexedCell) -> Result<(), Error> {
+  let nft_data = nft_cell.load_data().map_err(|_| rejected!(Error::NFTDataInvalid))?;
+  let nft = Nft::from_data(&nft_data)?;
+  let attributes = parse_attributes(nft.data_content())
+    .map_err(|_| rejected!(Error::NFTAttributesNotMatchSchema))?;
+  if !schema.is_satisfied_by(&attributes) {
+    return Err(rejected!(Error::NFTAttributesNotMatchSchema));
   }
+  Ok(())
+}
 
-  if nft_inputs_count == nft_outputs_count {
-    return Ok(Action::Update);
-  }
-  Err(Error::NFTCellsCountError)
+// A class dep with its schema, loaded once however many nfts of the class are minted
+struct MintedClass {
+  index: usize,
+  class: Class,
+  schema: Option<AttributeSchema>,
 }
 
-fn handle_creation(nft_type: &Script) -> Result<(), Error> {
-  // Check type Script Args
-  // Use first input output
-  let first_input = load_input(0, Source::Input)?;
-  let first_input_previous_output = first_input.previous_output();
-  let mut blake2b = Blake2bBuilder::new(32).build();
-  blake2b.update(first_input_previous_output.tx_hash().as_slice());
-  // Use this cell output index
-  let output_index = get_cell_output_index_by_type(nft_type)?;
-  blake2b.update(&output_index.to_le_bytes());
-  let mut ret = [0; 32];
-  blake2b.finalize(&mut ret);
-
-  // Check that the last 32 bytes is the expected hash
+fn handle_creation(nft_type: &Script, registry: &Registry) -> Result<(), Error> {
+  // Check that the last 32 bytes of type script args is the type id of this cell
   let nft_args: Bytes = nft_type.args().unpack();
-  if nft_args[24..56] != ret[0..32] {
-    return Err(Error::TypeArgsInvalid);
+  check_type_id(nft_type, &nft_args[CLASS_TYPE_ARGS_LEN..NFT_TYPE_ARGS_LEN])?;
+
+  // Every cell of the transaction is loaded once and served from the index
+  let tx = TxIndex::load();
+
+  // Get all nfts from issuer cell minted in this transaction, nfts paired with an input
+  // are updated by their own script group
+  let is_issuer_nft = check_issuer_type(nft_type);
+  let is_minted_nft = |type_: &Script| is_issuer_nft(type_) && !tx.has_type(Source::Input, type_);
+
+  // Every minted nft runs its own group, the batch of the issuer is validated only by the
+  // group of its first output so that minting n nfts costs n validations and not n²
+  let is_first_minted = tx
+    .find_by_type(Source::Output, &is_minted_nft)
+    .and_then(|nft_cell| nft_cell.type_.as_ref())
+    .map_or(false, |type_| type_.as_slice() == nft_type.as_slice());
+  if !is_first_minted {
+    return Ok(());
   }
-  
-  // Get all nfts from issuer cell
-  let output_nft_types = QueryIter::new(load_cell_type, Source::Output)
-    .filter(|type_opt| parse_type_opt(&type_opt, &check_issuer_type(nft_type)));
-  
+
+  let mut classes: Vec<MintedClass> = Vec::new();
   let mut total_cost: u64 = 0;
   let mut minted_nfts_total_occupied_capacity: u64 = 0;
-  for output_nft_type in output_nft_types {
-    let nft_type_script = output_nft_type.unwrap();
+  for nft_cell in tx.filter_by_type(Source::Output, &is_minted_nft) {
+    let nft_type_script = nft_cell.type_.as_ref().ok_or_else(|| rejected!(Error::NFTDataInvalid))?;
     let nft_args: Bytes = nft_type_script.args().unpack();
 
     // Check the class dependency exists for every output nft
-    let class_inputs_count = count_cells_by_type(Source::CellDep, &check_class_type(&nft_args));
-    if class_inputs_count != 1 {
-      return Err(Error::ClassCellsCountError);
-    }
+    let is_class = check_class_type(registry, &nft_args);
+    let mut class_cells = tx.filter_by_type(Source::CellDep, &is_class);
+    let class_cell = match (class_cells.next(), class_cells.next()) {
+      (Some(class_cell), None) => class_cell,
+      _ => return Err(rejected!(Error::ClassCellsCountError)),
+    };
 
-    // Load data from class cell
-    let load_class = |source| match load_cell_data_by_type(source, &check_class_type(&nft_args)) {
-      Some(data) => Ok(Class::from_data(&data)?),
-      None => Err(Error::ClassDataInvalid),
+    // Load data from class cell and its metadata cell
+    let class_position = match classes.iter().position(|class| class.index == class_cell.index) {
+      Some(position) => position,
+      None => {
+        let class_data = class_cell.load_data().map_err(|_| rejected!(Error::ClassDataInvalid))?;
+        let class = Class::from_data(&class_data)?;
+        trace!("class_loaded", cost = class.cost, flags = class.flags);
+        // NFTs of a class linked to a metadata cell must satisfy its freeze requirement
+        let schema = load_class_schema(&class)?;
+        classes.push(MintedClass {
+          index: class_cell.index,
+          class,
+          schema,
+        });
+        classes.len() - 1
+      }
     };
-    let class_data = load_class(Source::CellDep)?;
+    let minted_class = &classes[class_position];
 
-    // convert cost from CKB to Shannon
-    total_cost += class_data.cost * 100000000;
-    minted_nfts_total_occupied_capacity += get_cell_occupied_capacity_by_type(&nft_type_script);
+    // and the schema of the metadata cell
+    if let Some(schema) = &minted_class.schema {
+      validate_nft_attributes(schema, nft_cell)?;
+    }
 
+    // convert cost from CKB to Shannon
+    total_cost = minted_class
+      .class
+      .cost
+      .checked_mul(SHANNONS_PER_CKB)
+      .and_then(|cost| total_cost.checked_add(cost))
+      .ok_or_else(|| rejected!(Error::NFTCostOverflow))?;
+    minted_nfts_total_occupied_capacity = minted_nfts_total_occupied_capacity
+      .checked_add(nft_cell.load_occupied_capacity()?)
+      .ok_or_else(|| rejected!(Error::NFTCostOverflow))?;
   }
 
   // Load payment cell capacity
-  let payment_cell_capacity = load_payment_cell_capacity()?;
+  let payment_cell_capacity = tx
+    .find_by_lock(Source::Output, &|lock: &Script| registry.payment_lock.matches_with_args(lock))
+    .map(|payment_cell| payment_cell.capacity)
+    .ok_or_else(|| rejected!(Error::InvalidPaymentLockScript))?;
+  trace!(
+    "payment_computed",
+    paid = payment_cell_capacity,
+    cost = total_cost,
+    occupied = minted_nfts_total_occupied_capacity,
+  );
 
   // Check the cost of all nfts is being sent to seller address
-  if payment_cell_capacity < (total_cost-minted_nfts_total_occupied_capacity) {
-    return Err(Error::PaymentNotEnough);
+  if payment_cell_capacity < total_cost.saturating_sub(minted_nfts_total_occupied_capacity) {
+    return Err(rejected!(Error::PaymentNotEnough));
   }
 
   Ok(())
 }
 
 fn handle_update(nft_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(nft_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
-  }
+  check_nft_owner_auth(nft_type)?;
   let nft_data = (
     load_nft_data(Source::GroupInput)?,
     load_nft_data(Source::GroupOutput)?,
@@ -183,11 +227,44 @@ fn handle_update(nft_type: &Script) -> Result<(), Error> {
 }
 
 fn handle_destroying(nft_type: &Script) -> Result<(), Error> {
-  // Disable anyone-can-pay lock
-  if check_group_input_witness_is_none_with_type(nft_type)? {
-    return Err(Error::GroupInputWitnessNoneError);
+  check_nft_owner_auth(nft_type)?;
+
+  Ok(())
+}
+
+fn check_declared_action(action: &Action) -> Result<Option<CdmAction>, Error> {
+  let declared_action = match load_declared_action(action)? {
+    Some(declared_action) => declared_action,
+    None => return Ok(None),
+  };
+  trace!("action_declared", action = declared_action);
+  match &declared_action {
+    CdmAction::Mint { .. }
+    | CdmAction::Transfer
+    | CdmAction::Lock
+    | CdmAction::Claim
+    | CdmAction::Burn => {}
+    _ => return Err(rejected!(Error::ActionNotMatchTransition)),
   }
+  check_action_transition(action, &declared_action)?;
+  Ok(Some(declared_action))
+}
 
+// The declared sale price of a mint must be the class cost of this nft
+fn check_sale_price(
+  nft_type: &Script,
+  registry: &Registry,
+  declared_action: Option<&CdmAction>,
+) -> Result<(), Error> {
+  if let Some(CdmAction::Mint { sale_price }) = declared_action {
+    let nft_args: Bytes = nft_type.args().unpack();
+    let class_data = load_cell_data_by_type(Source::CellDep, &check_class_type(registry, &nft_args))
+      .ok_or_else(|| rejected!(Error::ClassCellsCountError))?;
+    let class = Class::from_data(&class_data)?;
+    if class.cost.checked_mul(SHANNONS_PER_CKB) != Some(*sale_price) {
+      return Err(rejected!(Error::ActionSalePriceInvalid));
+    }
+  }
   Ok(())
 }
 
@@ -195,11 +272,20 @@ pub fn main() -> Result<(), Error> {
   let nft_type = load_script()?;
   let nft_args: Bytes = nft_type.args().unpack();
   if nft_args.len() != NFT_TYPE_ARGS_LEN {
-    return Err(Error::TypeArgsInvalid);
+    return Err(rejected!(Error::TypeArgsInvalid));
   }
 
-  match parse_nft_action(&nft_type)? {
-    Action::Create => handle_creation(&nft_type),
+  let action = parse_group_action(Error::NFTCellsCountError)?;
+  trace!("action_detected", action = action);
+  let declared_action = check_declared_action(&action)?;
+  match action {
+    Action::Create => {
+      // Class and payment scripts are resolved from the deployment registry, loaded once
+      // for the group
+      let registry = load_registry()?;
+      check_sale_price(&nft_type, &registry, declared_action.as_ref())?;
+      handle_creation(&nft_type, &registry)
+    }
     Action::Update => handle_update(&nft_type),
     Action::Destroy => handle_destroying(&nft_type),
   }
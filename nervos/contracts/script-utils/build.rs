@@ -0,0 +1,106 @@
+//! Generates the type id of the registry cell of the selected network profile from the
+//! migration written by `capsule deploy`, see `src/network.rs`.
+//!
+//! The migration is `CDM_MIGRATION` when it is set, otherwise the latest one in
+//! `migrations/<profile>` of the workspace, where profile is mainnet, testnet or devnet.
+//! The build fails without a deployed registry, unless the `placeholder-registry` feature
+//! lets tests use an all zeros type id.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the registry cell in `deployment.toml`
+const REGISTRY_CELL_NAME: &str = "registry_cdm";
+const TYPE_ID_LEN: usize = 32;
+
+fn profile() -> &'static str {
+  if env::var_os("CARGO_FEATURE_MAINNET").is_some() {
+    "mainnet"
+  } else if env::var_os("CARGO_FEATURE_DEVNET").is_some() {
+    "devnet"
+  } else {
+    "testnet"
+  }
+}
+
+// Capsule names migrations after their date, so the last one is the current deployment
+fn latest_migration(dir: &Path) -> Option<PathBuf> {
+  let mut migrations: Vec<PathBuf> = fs::read_dir(dir)
+    .ok()?
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().map_or(false, |extension| extension == "json"))
+    .collect();
+  migrations.sort();
+  migrations.pop()
+}
+
+fn parse_type_id(hex: &str) -> Option<[u8; TYPE_ID_LEN]> {
+  let hex = hex.strip_prefix("0x")?;
+  if hex.len() != TYPE_ID_LEN * 2 {
+    return None;
+  }
+  let mut type_id = [0u8; TYPE_ID_LEN];
+  for (index, byte) in type_id.iter_mut().enumerate() {
+    *byte = u8::from_str_radix(hex.get((index * 2)..(index * 2 + 2))?, 16).ok()?;
+  }
+  Some(type_id)
+}
+
+/// Type id of the registry cell recorded in the migration, if the registry was deployed
+fn registry_type_id(migration: &Path) -> Option<[u8; TYPE_ID_LEN]> {
+  let content = fs::read_to_string(migration)
+    .unwrap_or_else(|err| panic!("cannot read {}: {}", migration.display(), err));
+  let json: serde_json::Value = serde_json::from_str(&content)
+    .unwrap_or_else(|err| panic!("invalid migration {}: {}", migration.display(), err));
+  let recipe = json["cell_recipes"]
+    .as_array()?
+    .iter()
+    .find(|recipe| recipe["name"] == REGISTRY_CELL_NAME)?;
+  let type_id = recipe["type_id"].as_str().and_then(parse_type_id);
+  if type_id.is_none() {
+    panic!("the {} cell must be deployed with enable_type_id", REGISTRY_CELL_NAME);
+  }
+  type_id
+}
+
+fn main() {
+  let profile = profile();
+  let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("manifest dir"));
+  let migrations_dir = manifest_dir.join("../../migrations").join(profile);
+  println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-changed={}", migrations_dir.display());
+  println!("cargo:rerun-if-env-changed=CDM_MIGRATION");
+
+  let migration = match env::var_os("CDM_MIGRATION") {
+    Some(path) => Some(PathBuf::from(path)),
+    None => latest_migration(&migrations_dir),
+  };
+  let type_id = migration.as_deref().and_then(registry_type_id);
+  // No registry cell can be created with the all zeros type id, so contracts built with
+  // the placeholder reject every transaction outside of tests. Mainnet binaries are never
+  // built with it.
+  let placeholder = env::var_os("CARGO_FEATURE_PLACEHOLDER_REGISTRY").is_some();
+  let type_id = match type_id {
+    Some(type_id) => type_id,
+    None if placeholder && profile != "mainnet" => {
+      println!(
+        "cargo:warning=no {} cell deployed on {}, REGISTRY_TYPE_ID is a placeholder",
+        REGISTRY_CELL_NAME, profile
+      );
+      [0u8; TYPE_ID_LEN]
+    }
+    None => panic!(
+      "no {} cell deployed on {}, deploy it or set CDM_MIGRATION",
+      REGISTRY_CELL_NAME, profile
+    ),
+  };
+
+  let out_dir = PathBuf::from(env::var("OUT_DIR").expect("out dir"));
+  let constants = format!(
+    "/// Type id of the registry cell of the network, generated by build.rs\n\
+     pub const REGISTRY_TYPE_ID: [u8; {}] = {:?};\n",
+    TYPE_ID_LEN, type_id
+  );
+  fs::write(out_dir.join("deployment.rs"), constants).expect("write deployment constants");
+}
@@ -0,0 +1,231 @@
+use crate::error::Error;
+use alloc::vec::Vec;
+use core::result::Result;
+#[cfg(feature = "contract")]
+use {
+  crate::helper::Action,
+  ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, prelude::*},
+    error::SysError,
+    high_level::{load_cell_lock_hash, load_witness_args},
+  },
+};
+
+const NUMBER_SIZE: usize = 4;
+const UINT64_LEN: usize = 8;
+pub const BYTE32_LEN: usize = 32;
+
+/// Action declared in the witness of a CDM cell, encoded as the CdmAction union of
+/// `schemas/cdm_action.mol`. Every parameter is verified against the transaction.
+///
+/// Actions carry no signature: the locks of the inputs already sign the whole transaction
+/// with its witnesses, the action included, and a type script cannot verify one without
+/// its own secp256k1 verifier. They carry no Merkle proof either, no CDM cell commits to
+/// a root a contract could check a proof against, so a proof would be unverified data.
+/// Both can be added as new union items once a cell holds what they are checked against.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CdmAction {
+  Mint {
+    sale_price: u64,
+  },
+  Transfer,
+  Burn,
+  Lock,
+  Claim,
+  UpdateMetadata {
+    prev_data_hash: [u8; BYTE32_LEN],
+  },
+  Update,
+}
+
+fn read_number(data: &[u8], offset: usize) -> Result<usize, Error> {
+  if data.len() < offset + NUMBER_SIZE {
+    return Err(Error::ActionWitnessInvalid);
+  }
+  let mut buf = [0u8; NUMBER_SIZE];
+  buf.copy_from_slice(&data[offset..(offset + NUMBER_SIZE)]);
+  Ok(u32::from_le_bytes(buf) as usize)
+}
+
+fn parse_table(data: &[u8], field_count: usize) -> Result<Vec<&[u8]>, Error> {
+  let total_size = read_number(data, 0)?;
+  if total_size != data.len() {
+    return Err(Error::ActionWitnessInvalid);
+  }
+  if field_count == 0 {
+    return match total_size {
+      NUMBER_SIZE => Ok(Vec::new()),
+      _ => Err(Error::ActionWitnessInvalid),
+    };
+  }
+
+  let first_offset = read_number(data, NUMBER_SIZE)?;
+  if first_offset != NUMBER_SIZE * (field_count + 1) {
+    return Err(Error::ActionWitnessInvalid);
+  }
+  let mut offsets = Vec::new();
+  for index in 0..field_count {
+    offsets.push(read_number(data, NUMBER_SIZE * (index + 1))?);
+  }
+  offsets.push(total_size);
+
+  let mut fields = Vec::new();
+  for pair in offsets.windows(2) {
+    if pair[0] > pair[1] || pair[1] > total_size {
+      return Err(Error::ActionWitnessInvalid);
+    }
+    fields.push(&data[pair[0]..pair[1]]);
+  }
+  Ok(fields)
+}
+
+fn parse_uint64(data: &[u8]) -> Result<u64, Error> {
+  if data.len() != UINT64_LEN {
+    return Err(Error::ActionWitnessInvalid);
+  }
+  let mut buf = [0u8; UINT64_LEN];
+  buf.copy_from_slice(data);
+  Ok(u64::from_le_bytes(buf))
+}
+
+fn parse_byte32(data: &[u8]) -> Result<[u8; BYTE32_LEN], Error> {
+  if data.len() != BYTE32_LEN {
+    return Err(Error::ActionWitnessInvalid);
+  }
+  let mut buf = [0u8; BYTE32_LEN];
+  buf.copy_from_slice(data);
+  Ok(buf)
+}
+
+fn to_number(number: usize) -> [u8; NUMBER_SIZE] {
+  (number as u32).to_le_bytes()
+}
+
+fn to_table(fields: &[Vec<u8>]) -> Vec<u8> {
+  let header_len = NUMBER_SIZE * (fields.len() + 1);
+  let total_size = header_len + fields.iter().map(|field| field.len()).sum::<usize>();
+  let mut data = to_number(total_size).to_vec();
+  let mut offset = header_len;
+  for field in fields {
+    data.extend_from_slice(&to_number(offset));
+    offset += field.len();
+  }
+  for field in fields {
+    data.extend_from_slice(field);
+  }
+  data
+}
+
+impl CdmAction {
+  /// Encodes the action as the CdmAction union, the inverse of from_slice
+  pub fn to_vec(&self) -> Vec<u8> {
+    let (item_id, fields) = match self {
+      CdmAction::Mint { sale_price } => (0, alloc::vec![sale_price.to_le_bytes().to_vec()]),
+      CdmAction::Transfer => (1, Vec::new()),
+      CdmAction::Burn => (2, Vec::new()),
+      CdmAction::Lock => (3, Vec::new()),
+      CdmAction::Claim => (4, Vec::new()),
+      CdmAction::UpdateMetadata { prev_data_hash } => (5, alloc::vec![prev_data_hash.to_vec()]),
+      CdmAction::Update => (6, Vec::new()),
+    };
+    let mut data = to_number(item_id).to_vec();
+    data.extend(to_table(&fields));
+    data
+  }
+
+  pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
+    let item_id = read_number(data, 0)?;
+    let item = &data[NUMBER_SIZE..];
+    match item_id {
+      0 => {
+        let fields = parse_table(item, 1)?;
+        Ok(CdmAction::Mint {
+          sale_price: parse_uint64(fields[0])?,
+        })
+      }
+      1 => parse_table(item, 0).map(|_| CdmAction::Transfer),
+      2 => parse_table(item, 0).map(|_| CdmAction::Burn),
+      3 => parse_table(item, 0).map(|_| CdmAction::Lock),
+      4 => parse_table(item, 0).map(|_| CdmAction::Claim),
+      5 => {
+        let fields = parse_table(item, 1)?;
+        Ok(CdmAction::UpdateMetadata {
+          prev_data_hash: parse_byte32(fields[0])?,
+        })
+      }
+      6 => parse_table(item, 0).map(|_| CdmAction::Update),
+      _ => Err(Error::ActionWitnessInvalid),
+    }
+  }
+}
+
+// Contracts built with the `legacy-actions` feature accept transitions without a
+// declaration, so that clients which do not declare actions yet keep working until the
+// contracts are deployed without it
+#[cfg(feature = "contract")]
+fn missing_action() -> Result<Option<CdmAction>, Error> {
+  if cfg!(feature = "legacy-actions") {
+    Ok(None)
+  } else {
+    Err(Error::ActionMissing)
+  }
+}
+
+/// Loads the action declared for the current script group. Creations declare it in
+/// the output_type of the witness of the first group output, updates and destructions
+/// in the input_type of the witness of the first group input. The declaration is
+/// mandatory, none is returned for an undeclared transition only with the
+/// `legacy-actions` feature, the contract then checks the transition as inferred from
+/// the cells.
+#[cfg(feature = "contract")]
+pub fn load_declared_action(action: &Action) -> Result<Option<CdmAction>, Error> {
+  let source = match action {
+    Action::Create => Source::GroupOutput,
+    _ => Source::GroupInput,
+  };
+  let witness_args = match load_witness_args(0, source) {
+    Ok(witness_args) => witness_args,
+    Err(SysError::IndexOutOfBound) => return missing_action(),
+    Err(SysError::Encoding) => return Err(Error::ActionWitnessInvalid),
+    Err(err) => return Err(err.into()),
+  };
+  let action_bytes = match action {
+    Action::Create => witness_args.output_type().to_opt(),
+    _ => witness_args.input_type().to_opt(),
+  };
+  match action_bytes {
+    Some(action_bytes) => {
+      let action_data: Bytes = action_bytes.unpack();
+      CdmAction::from_slice(&action_data[..]).map(Some)
+    }
+    None => missing_action(),
+  }
+}
+
+#[cfg(feature = "contract")]
+fn group_lock_changed() -> Result<bool, Error> {
+  Ok(load_cell_lock_hash(0, Source::GroupInput)? != load_cell_lock_hash(0, Source::GroupOutput)?)
+}
+
+/// Checks that the declared action agrees with the cell transition of the current script
+/// group, contract specific parameters are checked by each contract
+#[cfg(feature = "contract")]
+pub fn check_action_transition(action: &Action, declared_action: &CdmAction) -> Result<(), Error> {
+  let matched = match (action, declared_action) {
+    (Action::Create, CdmAction::Mint { .. }) => true,
+    (Action::Destroy, CdmAction::Burn) => true,
+    (Action::Update, CdmAction::Transfer) | (Action::Update, CdmAction::Claim) => {
+      group_lock_changed()?
+    }
+    (Action::Update, CdmAction::Lock)
+    | (Action::Update, CdmAction::UpdateMetadata { .. })
+    | (Action::Update, CdmAction::Update) => !group_lock_changed()?,
+    _ => false,
+  };
+  if !matched {
+    return Err(Error::ActionNotMatchTransition);
+  }
+  Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::error::Error;
+use crate::helper::{u16_from_slice, u64_from_slice};
+use alloc::vec::Vec;
+use core::result::Result;
+
+// type(u8) + length(u16)
+const RECORD_HEADER_LEN: usize = 3;
+const KEY_SIZE_LEN: usize = 1;
+pub const ATTRIBUTE_HASH_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum AttributeType {
+  String = 0,
+  U64,
+  Bool,
+  Hash,
+  Enum,
+}
+
+impl AttributeType {
+  pub fn from_u8(value: u8) -> Result<Self, Error> {
+    match value {
+      0 => Ok(AttributeType::String),
+      1 => Ok(AttributeType::U64),
+      2 => Ok(AttributeType::Bool),
+      3 => Ok(AttributeType::Hash),
+      4 => Ok(AttributeType::Enum),
+      _ => Err(Error::Encoding),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AttributeValue {
+  String(Vec<u8>),
+  U64(u64),
+  Bool(bool),
+  Hash([u8; ATTRIBUTE_HASH_LEN]),
+  Enum(u8),
+}
+
+impl AttributeValue {
+  fn from_slice(type_: AttributeType, value: &[u8]) -> Result<Self, Error> {
+    match (type_, value.len()) {
+      (AttributeType::String, _) => core::str::from_utf8(value)
+        .map(|_| AttributeValue::String(value.to_vec()))
+        .map_err(|_| Error::Encoding),
+      (AttributeType::U64, 8) => Ok(AttributeValue::U64(u64_from_slice(value))),
+      (AttributeType::Bool, 1) if value[0] <= 1 => Ok(AttributeValue::Bool(value[0] == 1)),
+      (AttributeType::Hash, ATTRIBUTE_HASH_LEN) => {
+        let mut hash = [0u8; ATTRIBUTE_HASH_LEN];
+        hash.copy_from_slice(value);
+        Ok(AttributeValue::Hash(hash))
+      }
+      (AttributeType::Enum, 1) => Ok(AttributeValue::Enum(value[0])),
+      _ => Err(Error::Encoding),
+    }
+  }
+
+  pub fn type_(&self) -> AttributeType {
+    match self {
+      AttributeValue::String(_) => AttributeType::String,
+      AttributeValue::U64(_) => AttributeType::U64,
+      AttributeValue::Bool(_) => AttributeType::Bool,
+      AttributeValue::Hash(_) => AttributeType::Hash,
+      AttributeValue::Enum(_) => AttributeType::Enum,
+    }
+  }
+}
+
+/// Attribute record structure
+/// Attributes are a sequence of records and every record contains:
+/// 1) type: u8, one of string, u64, bool, hash and enum
+/// 2) length: u16, the size of 3) and 4)
+/// 3) key: <size: u8> + <content>
+/// 4) value: string is utf8 content, u64 is 8 bytes big endian, bool is 1 byte of 0 or 1,
+///    hash is 32 bytes and enum is 1 byte variant
+/// Keys cannot be repeated in the same sequence.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute {
+  pub key: Vec<u8>,
+  pub value: AttributeValue,
+}
+
+pub fn parse_attributes(data: &[u8]) -> Result<Vec<Attribute>, Error> {
+  let mut attributes: Vec<Attribute> = Vec::new();
+  let mut index = 0;
+  while index < data.len() {
+    if data.len() < index + RECORD_HEADER_LEN {
+      return Err(Error::Encoding);
+    }
+    let type_ = AttributeType::from_u8(data[index])?;
+    let record_len = u16_from_slice(&data[(index + 1)..(index + RECORD_HEADER_LEN)]) as usize;
+    let record_index = index + RECORD_HEADER_LEN;
+    if record_len < KEY_SIZE_LEN || data.len() < record_index + record_len {
+      return Err(Error::Encoding);
+    }
+    let record = &data[record_index..(record_index + record_len)];
+
+    let key_len = record[0] as usize;
+    if record.len() < KEY_SIZE_LEN + key_len {
+      return Err(Error::Encoding);
+    }
+    let key = &record[KEY_SIZE_LEN..(KEY_SIZE_LEN + key_len)];
+    if attributes.iter().any(|attribute| attribute.key == key) {
+      return Err(Error::Encoding);
+    }
+    let value = AttributeValue::from_slice(type_, &record[(KEY_SIZE_LEN + key_len)..])?;
+
+    attributes.push(Attribute {
+      key: key.to_vec(),
+      value,
+    });
+    index = record_index + record_len;
+  }
+  Ok(attributes)
+}
+
+/// Attribute schema structure
+/// The schema is a sequence of entries and every entry contains:
+/// 1) type: u8
+/// 2) key: <size: u8> + <content>
+/// Attributes satisfy the schema when every key of the schema exists with the same type.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeSchema {
+  pub entries: Vec<(Vec<u8>, AttributeType)>,
+}
+
+impl AttributeSchema {
+  pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+    let mut entries: Vec<(Vec<u8>, AttributeType)> = Vec::new();
+    let mut index = 0;
+    while index < data.len() {
+      if data.len() < index + 1 + KEY_SIZE_LEN {
+        return Err(Error::Encoding);
+      }
+      let type_ = AttributeType::from_u8(data[index])?;
+      let key_len = data[index + 1] as usize;
+      let key_index = index + 1 + KEY_SIZE_LEN;
+      if data.len() < key_index + key_len {
+        return Err(Error::Encoding);
+      }
+      let key = &data[key_index..(key_index + key_len)];
+      if entries.iter().any(|(entry_key, _)| entry_key == key) {
+        return Err(Error::Encoding);
+      }
+      entries.push((key.to_vec(), type_));
+      index = key_index + key_len;
+    }
+    Ok(AttributeSchema { entries })
+  }
+
+  pub fn to_data(&self) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (key, type_) in &self.entries {
+      data.push(*type_ as u8);
+      data.push(key.len() as u8);
+      data.extend_from_slice(key);
+    }
+    data
+  }
+
+  pub fn is_satisfied_by(&self, attributes: &[Attribute]) -> bool {
+    self.entries.iter().all(|(key, type_)| {
+      attributes
+        .iter()
+        .any(|attribute| &attribute.key == key && attribute.value.type_() == *type_)
+    })
+  }
+}
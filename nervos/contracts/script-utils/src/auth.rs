@@ -0,0 +1,84 @@
+use crate::error::Error;
+use crate::helper::LOCK_HASH_LEN;
+use alloc::{vec, vec::Vec};
+use ckb_std::{
+  ckb_constants::Source,
+  ckb_types::{packed::*, prelude::*},
+  high_level::{load_cell_lock, load_cell_lock_hash, load_cell_type, load_witness_args, QueryIter},
+};
+use core::result::Result;
+
+/// Ways the owner of a group input cell can authorize spending it, on top of its lock.
+///
+/// A type script cannot see whether a lock verified a signature. These checks only show
+/// that a witness lock is present or that an input under some lock is consumed, which is
+/// the consent of the owner only when that lock verifies a signature. Owners of cells
+/// under locks unlocking without them, such as anyone-can-pay, set an owner lock hash to
+/// a signing lock of theirs.
+pub enum OwnerCheck<'a> {
+  /// An input of the lock group of the cell carries a non-empty witness lock. It is not a
+  /// proof, an anyone-can-pay lock never reads its witness so anyone can fill it, and is
+  /// only accepted for cells without an owner lock hash.
+  WitnessSignature,
+  /// An input locked by this lock hash is consumed. The hash is the owner lock hash field
+  /// of the cell data, the owner is responsible for it being a lock which signs.
+  OwnerLockHash([u8; LOCK_HASH_LEN]),
+  /// An input locked by the lock of a cell dep accepted by the predicate is consumed, so
+  /// the owner of that cell dep, such as the issuer, acts for the owner of the cell. Like
+  /// the owner lock hash, it only shows the input was consumed. It gives the owner of the
+  /// cell dep control over the cell, NFTs only accept the class owner when the class was
+  /// created with the owner delegate flag.
+  Delegate(&'a dyn Fn(&Script) -> bool),
+}
+
+impl<'a> OwnerCheck<'a> {
+  fn is_satisfied(&self, lock: &Script) -> bool {
+    match self {
+      OwnerCheck::WitnessSignature => lock_group_has_witness_lock(lock),
+      OwnerCheck::OwnerLockHash(lock_hash) => inputs_have_lock_hash(lock_hash),
+      OwnerCheck::Delegate(predicate) => QueryIter::new(load_cell_type, Source::CellDep)
+        .enumerate()
+        .filter(|(_, type_opt)| type_opt.as_ref().map_or(false, |type_| predicate(type_)))
+        .any(|(index, _)| {
+          load_cell_lock_hash(index, Source::CellDep)
+            .map_or(false, |lock_hash| inputs_have_lock_hash(&lock_hash))
+        }),
+    }
+  }
+}
+
+fn inputs_have_lock_hash(lock_hash: &[u8; LOCK_HASH_LEN]) -> bool {
+  QueryIter::new(load_cell_lock_hash, Source::Input).any(|input_lock_hash| &input_lock_hash == lock_hash)
+}
+
+fn lock_group_has_witness_lock(lock: &Script) -> bool {
+  QueryIter::new(load_cell_lock, Source::Input)
+    .enumerate()
+    .filter(|(_, input_lock)| input_lock.as_slice() == lock.as_slice())
+    .any(|(index, _)| {
+      load_witness_args(index, Source::Input).map_or(false, |witness_args| {
+        let witness_lock = witness_args.lock().to_opt();
+        witness_lock.map_or(false, |witness_lock| !witness_lock.raw_data().is_empty())
+      })
+    })
+}
+
+/// The owner lock hash check if the cell data has one, so a filled witness lock cannot
+/// stand in for the owner, otherwise the witness lock check
+pub fn owner_checks<'a>(owner_lock_hash: Option<[u8; LOCK_HASH_LEN]>) -> Vec<OwnerCheck<'a>> {
+  match owner_lock_hash {
+    Some(lock_hash) => vec![OwnerCheck::OwnerLockHash(lock_hash)],
+    None => vec![OwnerCheck::WitnessSignature],
+  }
+}
+
+/// Checks that every input cell of the current script group is authorized by its owner
+/// through at least one of the checks
+pub fn check_owner_auth(checks: &[OwnerCheck]) -> Result<(), Error> {
+  let group_locks: Vec<Script> = QueryIter::new(load_cell_lock, Source::GroupInput).collect();
+  if group_locks.iter().all(|lock| checks.iter().any(|check| check.is_satisfied(lock))) {
+    Ok(())
+  } else {
+    Err(Error::OwnerAuthorizationMissing)
+  }
+}
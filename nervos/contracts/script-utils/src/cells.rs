@@ -0,0 +1,105 @@
+//! Helpers that read the transaction through syscalls, built with the contract feature
+
+use crate::error::Error;
+use crate::helper::{hash_types_match, Action};
+use alloc::vec::Vec;
+use ckb_std::{
+  ckb_constants::Source,
+  ckb_types::{bytes::Bytes, packed::*, prelude::*},
+  high_level::{load_cell_data, load_cell_type, load_cell_type_hash, QueryIter},
+};
+use core::result::Result;
+
+const ID_LEN: usize = 4;
+
+/// Whether both scripts run the same code, regardless of their args
+pub fn is_same_code(script: &Script, other: &Script) -> bool {
+  script.code_hash().as_slice() == other.code_hash().as_slice()
+    && hash_types_match(script.hash_type().as_slice()[0], other.hash_type().as_slice()[0])
+}
+
+/// Parses the action of the current script group from its own cells. Cells of other
+/// scripts, including cells of the same code with different args, do not affect it, so
+/// several cells can be created or updated in one transaction as long as their type ids
+/// are different.
+pub fn parse_group_action(cells_count_error: Error) -> Result<Action, Error> {
+  let count_cells = |source| QueryIter::new(load_cell_type, source).count();
+  match (count_cells(Source::GroupInput), count_cells(Source::GroupOutput)) {
+    (0, 1) => Ok(Action::Create),
+    (1, 1) => Ok(Action::Update),
+    (1, 0) => Ok(Action::Destroy),
+    _ => Err(cells_count_error),
+  }
+}
+
+fn parse_type_args_id(type_script: Script, slice_start: usize) -> Option<u32> {
+  let type_args: Bytes = type_script.args().unpack();
+  let id_slice = &type_args[slice_start..];
+  if id_slice.len() != ID_LEN {
+    return None;
+  }
+  let mut ids = [0u8; ID_LEN];
+  ids.copy_from_slice(&id_slice[..]);
+  Some(u32::from_be_bytes(ids))
+}
+
+fn parse_type_opt(type_opt: &Option<Script>, predicate: &dyn Fn(&Script) -> bool) -> bool {
+  match type_opt {
+    Some(type_) => predicate(type_),
+    None => false,
+  }
+}
+
+/// Whether an input cell has exactly this type script, i.e. an output with this type
+/// script is paired with an input instead of being created
+pub fn inputs_have_type(type_script: &Script) -> bool {
+  QueryIter::new(load_cell_type, Source::Input)
+    .any(|type_opt| type_opt.map_or(false, |type_| type_.as_slice() == type_script.as_slice()))
+}
+
+pub fn count_cells_by_type_hash(source: Source, predicate: &dyn Fn(&[u8]) -> bool) -> usize {
+  QueryIter::new(load_cell_type_hash, source)
+    .filter(|type_hash_opt| type_hash_opt.map_or(false, |type_hash| predicate(&type_hash)))
+    .count()
+}
+
+pub fn load_output_index_by_type(type_script: &Script) -> Option<usize> {
+  QueryIter::new(load_cell_type, Source::Output)
+    .position(|type_opt| type_opt.map_or(false, |type_| type_.as_slice() == type_script.as_slice()))
+}
+
+pub fn load_cell_data_by_type(
+  source: Source,
+  predicate: &dyn Fn(&Script) -> bool,
+) -> Option<Vec<u8>> {
+  QueryIter::new(load_cell_type, source)
+    .position(|type_opt| type_opt.map_or(false, |type_| predicate(&type_)))
+    .map(|index| load_cell_data(index, source).map_or_else(|_| Vec::new(), |data| data))
+}
+
+pub fn load_cells_data_by_type(source: Source, predicate: &dyn Fn(&Script) -> bool) -> Vec<Vec<u8>> {
+  QueryIter::new(load_cell_type, source)
+    .enumerate()
+    .filter(|(_, type_opt)| parse_type_opt(&type_opt, predicate))
+    .map(|(index, _)| load_cell_data(index, source).map_or_else(|_| Vec::new(), |data| data))
+    .collect()
+}
+
+pub fn load_cell_data_by_type_hash(
+  source: Source,
+  predicate: &dyn Fn(&[u8]) -> bool,
+) -> Option<Vec<u8>> {
+  QueryIter::new(load_cell_type_hash, source)
+    .position(|type_hash_opt| type_hash_opt.map_or(false, |type_hash| predicate(&type_hash)))
+    .map(|index| load_cell_data(index, source).map_or_else(|_| Vec::new(), |data| data))
+}
+
+pub fn load_output_type_args_ids(
+  slice_start: usize,
+  predicate: &dyn Fn(&Script) -> bool,
+) -> Vec<u32> {
+  QueryIter::new(load_cell_type, Source::Output)
+    .filter(|type_opt| parse_type_opt(&type_opt, predicate))
+    .filter_map(|type_opt| type_opt.and_then(|type_| parse_type_args_id(type_, slice_start)))
+    .collect()
+}
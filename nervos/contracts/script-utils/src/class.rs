@@ -1,13 +1,25 @@
 use crate::error::Error;
-use crate::helper::{parse_dyn_vec_len, u64_from_slice, DYN_MIN_LEN};
+use crate::helper::{DYN_MIN_LEN, LOCK_HASH_LEN};
+use crate::reader::DataReader;
 use alloc::vec::Vec;
+use core::ops::Range;
 use core::result::Result;
+#[cfg(feature = "contract")]
+use {
+  crate::reader::{range_equal, CellDataReader},
+  ckb_std::ckb_constants::Source,
+};
 
 const FIXED_LEN: usize = 9;
 
 // FIXED_LEN + DYN_MIN_LEN * 2
 const CLASS_DATA_MIN_LEN: usize = 13;
 pub const CLASS_TYPE_ARGS_LEN: usize = 24;
+const METADATA_TYPE_HASH_LEN: usize = 32;
+
+pub const CLASS_FLAG_REQUIRE_FROZEN_METADATA: u8 = 0b0000_0001;
+pub const CLASS_FLAG_OWNER_LOCK_HASH: u8 = 0b0000_0010;
+pub const CLASS_FLAG_OWNER_DELEGATE: u8 = 0b0000_0100;
 
 /// Class cell data structure
 /// This structure contains the following information:
@@ -17,62 +29,228 @@ pub const CLASS_TYPE_ARGS_LEN: usize = 24;
 /// 4) description: <size: u16> + <content>
 /// 5) meta_data_cell_type_hash: <size: u16> + <content>
 /// 6) renderer: <size: u16> + <content>
+/// 7) flags: u8
+/// 8) owner_lock_hash: [u8; 32]
 /// The fields of 3), 4) cannot be changed after they are set and they cannot be
 /// missing. The fields of 1) and 2) can be changed and it cannot be missing.
-/// The filed of 5) can be changed and it also can be missing and it will not be validated.
+/// The field of 5) can be missing and it can be changed, also after NFTs are minted. The
+/// class update does not check it, every mint does: the linked metadata cell must be a
+/// cell dep and the minted NFTs must satisfy its schema. NFTs minted before a change
+/// keep the attributes they were checked with.
+/// The fields of 6) and 7) can be changed and they can be missing, 7) requires 6) to exist.
+/// If the require frozen metadata flag is set, NFTs can be minted only when the linked
+/// metadata cell is frozen. The flag cannot be cleared once it is set.
+/// The field of 8) exists only if the owner lock hash flag is set, an input locked by it
+/// then authorizes spending the class as well as a witness lock.
+/// If the owner delegate flag is set, an input locked by the lock of the class cell
+/// authorizes updating and destroying the NFTs of the class for their holders. The flag
+/// can only be set when the class is created, so holders know it before they mint.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Class {
   pub version: u8,
   pub cost: u64,
   pub name: Vec<u8>,
   pub description: Vec<u8>,
   pub meta_data_cell_type_hash: Vec<u8>,
+  pub renderer: Vec<u8>,
+  pub flags: u8,
+  pub owner_lock_hash: Option<[u8; LOCK_HASH_LEN]>,
 }
 
-impl Class {
-  pub fn from_data(data: &[u8]) -> Result<Self, Error> {
-    if data.len() < CLASS_DATA_MIN_LEN {
+/// Offsets of the class fields, found from their size prefixes without copying the
+/// fields. Every range includes the size prefix of its field.
+#[derive(Debug, Clone)]
+pub struct ClassLayout {
+  pub version: u8,
+  pub cost: u64,
+  pub name: Range<usize>,
+  pub description: Range<usize>,
+  pub meta_data_cell_type_hash: Range<usize>,
+  pub renderer: Range<usize>,
+  pub flags: u8,
+  pub owner_lock_hash: Option<[u8; LOCK_HASH_LEN]>,
+}
+
+impl ClassLayout {
+  pub fn parse<R: DataReader + ?Sized>(data: &R) -> Result<Self, Error> {
+    let data_len = data.len();
+    if data_len < CLASS_DATA_MIN_LEN {
       return Err(Error::ClassDataInvalid);
     }
 
-    let version: u8 = data[0];
+    let version: u8 = data.read_u8(0)?;
     if version != 0 {
       return Err(Error::VersionInvalid);
     }
 
-    let cost = u64_from_slice(&data[1..9]);
+    let cost = data.read_u64(1)?;
 
-    let name_len = parse_dyn_vec_len(&data[FIXED_LEN..(FIXED_LEN + DYN_MIN_LEN)]);
+    let name_len = data.read_dyn_len(FIXED_LEN)?;
     // DYN_MIN_LEN: the min length of description
-    if data.len() < FIXED_LEN + name_len + DYN_MIN_LEN {
+    if data_len < FIXED_LEN + name_len + DYN_MIN_LEN {
       return Err(Error::ClassDataInvalid);
     }
-    let name = data[FIXED_LEN..(FIXED_LEN + name_len)].to_vec();
+    let name = FIXED_LEN..(FIXED_LEN + name_len);
 
-    let description_index = FIXED_LEN + name_len;
-    let description_len =
-      parse_dyn_vec_len(&data[description_index..(description_index + DYN_MIN_LEN)]);
+    let description_index = name.end;
+    let description_len = data.read_dyn_len(description_index)?;
     // DYN_MIN_LEN: the min length of meta_data_cell_type_hash
-    if data.len() < description_index + description_len + DYN_MIN_LEN {
+    if data_len < description_index + description_len + DYN_MIN_LEN {
       return Err(Error::ClassDataInvalid);
     }
-    let description = data[description_index..(description_index + description_len)].to_vec();
+    let description = description_index..(description_index + description_len);
 
-    let meta_data_cell_type_hash_index = FIXED_LEN + name_len + description_len;
-    let meta_data_cell_type_hash_len =
-      parse_dyn_vec_len(&data[meta_data_cell_type_hash_index..(meta_data_cell_type_hash_index + DYN_MIN_LEN)]);
-    let meta_data_cell_type_hash = data[meta_data_cell_type_hash_index..(meta_data_cell_type_hash_index + meta_data_cell_type_hash_len)].to_vec();
+    let meta_data_cell_type_hash_index = description.end;
+    let meta_data_cell_type_hash_len = data.read_dyn_len(meta_data_cell_type_hash_index)?;
+    if data_len < meta_data_cell_type_hash_index + meta_data_cell_type_hash_len {
+      return Err(Error::ClassDataInvalid);
+    }
+    let meta_data_cell_type_hash =
+      meta_data_cell_type_hash_index..(meta_data_cell_type_hash_index + meta_data_cell_type_hash_len);
 
-    Ok(Class {
+    let renderer_index = meta_data_cell_type_hash.end;
+    let renderer = if data_len > renderer_index {
+      if data_len < renderer_index + DYN_MIN_LEN {
+        return Err(Error::ClassDataInvalid);
+      }
+      let renderer_len = data.read_dyn_len(renderer_index)?;
+      if data_len < renderer_index + renderer_len {
+        return Err(Error::ClassDataInvalid);
+      }
+      renderer_index..(renderer_index + renderer_len)
+    } else {
+      renderer_index..renderer_index
+    };
+
+    let flags = if data_len > renderer.end { data.read_u8(renderer.end)? } else { 0 };
+
+    let owner_index = renderer.end + 1;
+    let has_owner_lock_hash = flags & CLASS_FLAG_OWNER_LOCK_HASH == CLASS_FLAG_OWNER_LOCK_HASH;
+    let owner_lock_hash = if has_owner_lock_hash {
+      if data_len < owner_index + LOCK_HASH_LEN {
+        return Err(Error::ClassDataInvalid);
+      }
+      let mut owner_lock_hash = [0u8; LOCK_HASH_LEN];
+      data.read(owner_index, &mut owner_lock_hash)?;
+      Some(owner_lock_hash)
+    } else {
+      None
+    };
+
+    Ok(ClassLayout {
       version,
       cost,
       name,
       description,
       meta_data_cell_type_hash,
+      renderer,
+      flags,
+      owner_lock_hash,
+    })
+  }
+}
+
+// Holders rely on the metadata of their NFTs staying as it was minted, and on the class
+// owner not gaining control over the NFTs they hold
+fn weakens_holders(flags: u8, other_flags: u8) -> bool {
+  let cleared = flags & !other_flags;
+  let set = !flags & other_flags;
+  cleared & CLASS_FLAG_REQUIRE_FROZEN_METADATA == CLASS_FLAG_REQUIRE_FROZEN_METADATA
+    || set & CLASS_FLAG_OWNER_DELEGATE == CLASS_FLAG_OWNER_DELEGATE
+}
+
+impl Class {
+  pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+    let layout = ClassLayout::parse(data)?;
+    Ok(Class {
+      version: layout.version,
+      cost: layout.cost,
+      name: data[layout.name].to_vec(),
+      description: data[layout.description].to_vec(),
+      meta_data_cell_type_hash: data[layout.meta_data_cell_type_hash].to_vec(),
+      renderer: data[layout.renderer].to_vec(),
+      flags: layout.flags,
+      owner_lock_hash: layout.owner_lock_hash,
     })
   }
 
+  /// Encodes the class, the dynamic fields keep their size prefixes and flags are only
+  /// written with a renderer, empty if missing. The owner lock hash is written when
+  /// present, flags must be set accordingly.
+  pub fn to_data(&self) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.push(self.version);
+    data.extend_from_slice(&self.cost.to_be_bytes());
+    data.extend_from_slice(&self.name);
+    data.extend_from_slice(&self.description);
+    data.extend_from_slice(&self.meta_data_cell_type_hash);
+    if !self.renderer.is_empty() {
+      data.extend_from_slice(&self.renderer);
+    } else if self.flags != 0 {
+      data.extend_from_slice(&[0u8; DYN_MIN_LEN]);
+    }
+    if self.flags != 0 {
+      data.push(self.flags);
+    }
+    if let Some(owner_lock_hash) = &self.owner_lock_hash {
+      data.extend_from_slice(owner_lock_hash);
+    }
+    data
+  }
+
+  pub fn requires_frozen_metadata(&self) -> bool {
+    self.flags & CLASS_FLAG_REQUIRE_FROZEN_METADATA == CLASS_FLAG_REQUIRE_FROZEN_METADATA
+  }
+
+  /// Whether the owner of the class can act for the holders of its NFTs
+  pub fn delegates_to_owner(&self) -> bool {
+    self.flags & CLASS_FLAG_OWNER_DELEGATE == CLASS_FLAG_OWNER_DELEGATE
+  }
+
+  /// Returns the type hash of the linked metadata cell, if the class has one
+  pub fn metadata_type_hash(&self) -> Option<&[u8]> {
+    let content = &self.meta_data_cell_type_hash[DYN_MIN_LEN..];
+    match content.len() {
+      METADATA_TYPE_HASH_LEN => Some(content),
+      _ => None,
+    }
+  }
+
   pub fn immutable_equal(&self, other: &Class) -> bool {
-    self.name == other.name && self.description == other.description
+    self.name == other.name
+      && self.description == other.description
+      && !weakens_holders(self.flags, other.flags)
+  }
+}
+
+/// Class cell read in place, only its fixed fields and size prefixes are loaded so that
+/// large names and descriptions are compared without copying them
+#[cfg(feature = "contract")]
+pub struct ClassReader {
+  pub cell: CellDataReader,
+  pub layout: ClassLayout,
+}
+
+#[cfg(feature = "contract")]
+impl ClassReader {
+  pub fn load(index: usize, source: Source) -> Result<Self, Error> {
+    let cell = CellDataReader::load(index, source).map_err(|_| Error::ClassDataInvalid)?;
+    let layout = ClassLayout::parse(&cell)?;
+    Ok(ClassReader { cell, layout })
+  }
+
+  pub fn immutable_equal(&self, other: &ClassReader) -> Result<bool, Error> {
+    let (layout, other_layout) = (&self.layout, &other.layout);
+    Ok(
+      range_equal(&self.cell, layout.name.clone(), &other.cell, other_layout.name.clone())?
+        && range_equal(
+          &self.cell,
+          layout.description.clone(),
+          &other.cell,
+          other_layout.description.clone(),
+        )?
+        && !weakens_holders(layout.flags, other_layout.flags),
+    )
   }
 }
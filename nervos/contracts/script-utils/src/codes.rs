@@ -0,0 +1,203 @@
+//! Error codes returned by the CDM contracts. This file has no dependencies so that the
+//! off-chain decoder crate can include it and always agree with the contracts.
+//!
+//! Each contract returns its errors in its own range of exit codes, so an exit code alone
+//! tells which contract failed and why:
+//!
+//! | contract    | exit codes   |
+//! |-------------|--------------|
+//! | issuer-type | 1 to 63      |
+//! | class-type  | 64 to 126    |
+//! | nft-type    | -2 to -64    |
+//! | metadata    | -65 to -127  |
+//!
+//! An error has the same id in every contract and the id is its offset in the range, so
+//! `IssuerClassCountError` (id 8) is 8 from issuer-type, 71 from class-type, -9 from
+//! nft-type and -72 from metadata. -1 is left to the panic handler of ckb-std. Ids go up
+//! to 63, new errors are appended and an id never changes its meaning.
+
+/// CDM contract returning an exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contract {
+  IssuerType,
+  ClassType,
+  NftType,
+  Metadata,
+}
+
+impl Contract {
+  pub const ALL: &'static [Contract] = &[
+    Contract::IssuerType,
+    Contract::ClassType,
+    Contract::NftType,
+    Contract::Metadata,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Contract::IssuerType => "issuer-type",
+      Contract::ClassType => "class-type",
+      Contract::NftType => "nft-type",
+      Contract::Metadata => "metadata",
+    }
+  }
+
+  /// Exit code of the error with id 1 and the step from one id to the next
+  fn range(&self) -> (i16, i16) {
+    match self {
+      Contract::IssuerType => (1, 1),
+      Contract::ClassType => (64, 1),
+      Contract::NftType => (-2, -1),
+      Contract::Metadata => (-65, -1),
+    }
+  }
+
+  /// Exit code of the error when this contract returns it
+  pub fn exit_code(&self, error: Error) -> i8 {
+    let (first, step) = self.range();
+    (first + step * (error.id() as i16 - 1)) as i8
+  }
+
+  /// Contract and error of an exit code, none for codes outside the ranges or without
+  /// an error
+  pub fn decode(code: i8) -> Option<(Contract, Error)> {
+    Contract::ALL.iter().find_map(|contract| {
+      let (first, step) = contract.range();
+      // Steps are 1 or -1, so multiplying by the step divides by it
+      let id = (code as i16 - first) * step + 1;
+      if id < 1 || id > Error::MAX_ID as i16 {
+        return None;
+      }
+      Error::from_id(id as i8).map(|error| (*contract, error))
+    })
+  }
+}
+
+/// Part of the protocol an error code belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+  Syscall,
+  Common,
+  Issuer,
+  Class,
+  Nft,
+  Metadata,
+  Payment,
+  Registry,
+  Action,
+  Auth,
+}
+
+impl Namespace {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Namespace::Syscall => "syscall",
+      Namespace::Common => "common",
+      Namespace::Issuer => "issuer",
+      Namespace::Class => "class",
+      Namespace::Nft => "nft",
+      Namespace::Metadata => "metadata",
+      Namespace::Payment => "payment",
+      Namespace::Registry => "registry",
+      Namespace::Action => "action",
+      Namespace::Auth => "auth",
+    }
+  }
+}
+
+macro_rules! error_codes {
+  ($($variant:ident = $id:literal, $namespace:ident, $explanation:literal;)*) => {
+    /// Error
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(i8)]
+    pub enum Error {
+      $($variant = $id,)*
+    }
+
+    impl Error {
+      pub const ALL: &'static [Error] = &[$(Error::$variant,)*];
+
+      /// Largest id an error can have, the size of the exit code range of a contract
+      pub const MAX_ID: i8 = 63;
+
+      pub fn from_id(id: i8) -> Option<Error> {
+        match id {
+          $($id => Some(Error::$variant),)*
+          _ => None,
+        }
+      }
+
+      /// Offset of the error in the exit code range of a contract, see
+      /// `Contract::exit_code`
+      pub fn id(&self) -> i8 {
+        *self as i8
+      }
+
+      pub fn name(&self) -> &'static str {
+        match self {
+          $(Error::$variant => stringify!($variant),)*
+        }
+      }
+
+      pub fn namespace(&self) -> Namespace {
+        match self {
+          $(Error::$variant => Namespace::$namespace,)*
+        }
+      }
+
+      pub fn explanation(&self) -> &'static str {
+        match self {
+          $(Error::$variant => $explanation,)*
+        }
+      }
+    }
+  };
+}
+
+error_codes! {
+  IndexOutOfBound = 1, Syscall, "A cell, input or witness was read past the end of the transaction";
+  ItemMissing = 2, Syscall, "A cell field that the contract needs, such as a type script, is missing";
+  LengthNotEnough = 3, Syscall, "A syscall buffer was too small for the loaded data";
+  Encoding = 4, Syscall, "Cell or witness data is not encoded as the contract expects";
+  IssuerDataInvalid = 5, Issuer, "The issuer cell data cannot be parsed";
+  IssuerCellsCountError = 6, Issuer, "The transaction does not have exactly one issuer cell where one is required";
+  TypeArgsInvalid = 7, Common, "The type script args have the wrong length or do not match the type id of the cell";
+  IssuerClassCountError = 8, Issuer, "The class count of the issuer cell decreased or does not match the created classes";
+  IssuerCellCannotDestroyed = 9, Issuer, "An issuer cell that still has classes cannot be destroyed";
+  VersionInvalid = 10, Common, "The data version of the cell is not supported";
+  ClassDataInvalid = 11, Class, "The class cell data cannot be parsed or the class cell is missing";
+  ClassCellsCountError = 12, Class, "The transaction does not have exactly one class cell where one is required";
+  ClassImmutableFieldsNotSame = 13, Class, "An immutable field of the class cell was modified";
+  ClassIdIncreaseError = 14, Class, "Created class ids do not follow the class count of the issuer";
+  NFTDataInvalid = 15, Nft, "The nft cell data cannot be parsed";
+  NFTCellsCountError = 16, Nft, "The nft script group does not create, update or destroy exactly one cell";
+  NFTDataNotSame = 17, Nft, "Immutable nft data was modified";
+  MetadataCellsCountError = 18, Metadata, "The metadata script group does not create, update or destroy exactly one cell";
+  MetadataImmutableFieldsNotSame = 19, Metadata, "An immutable field of the metadata cell was modified";
+  MetadataDataInvalid = 20, Metadata, "The metadata cell data cannot be parsed";
+  PaymentNotEnough = 21, Payment, "The payment output is smaller than the cost of the minted nfts";
+  InvalidPaymentLockScript = 22, Payment, "No output is locked by the payment lock";
+  MetadataHistoryInvalid = 23, Metadata, "The metadata history is missing or invalid";
+  MetadataRevisionIncreaseError = 24, Metadata, "The metadata revision must increase by one on each update";
+  MetadataPrevHashNotSame = 25, Metadata, "The previous data hash of the metadata history does not match the input";
+  MetadataChunkInvalid = 26, Metadata, "The metadata chunk is invalid";
+  MetadataChunkCountError = 27, Metadata, "The chunks of a metadata document disagree on the chunk count or are incomplete";
+  MetadataChunkOrderError = 28, Metadata, "The chunks of a metadata document are not in ascending order";
+  MetadataAttributesInvalid = 29, Metadata, "The metadata attributes cannot be parsed";
+  NFTAttributesNotMatchSchema = 30, Nft, "The nft attributes do not satisfy the schema of the class metadata";
+  NFTMetadataCellMissing = 31, Nft, "The metadata cell of the class is not a cell dep";
+  MetadataFrozenCannotModify = 32, Metadata, "A frozen metadata cell cannot be modified";
+  MetadataFrozenCannotDestroy = 33, Metadata, "A frozen metadata cell cannot be destroyed";
+  NFTMetadataNotFrozen = 34, Nft, "The class requires frozen metadata but its metadata cell is not frozen";
+  RegistryDataInvalid = 35, Registry, "The registry cell data cannot be parsed";
+  IssuerTypeInvalid = 36, Registry, "The issuer cell does not run the registered issuer type";
+  ActionWitnessInvalid = 37, Action, "The action declared in the witness cannot be parsed";
+  ActionNotMatchTransition = 38, Action, "The declared action does not match the cell transition";
+  ActionSalePriceInvalid = 39, Action, "The declared sale price does not match the class cost";
+  ActionPrevDataHashNotSame = 40, Action, "The declared previous data hash does not match the input metadata";
+  OwnerAuthorizationMissing = 41, Auth, "No owner authorization was found for an input cell";
+  UnknownSysError = 42, Syscall, "A syscall failed with an error code unknown to the contract";
+  RegistryCellMissing = 43, Registry, "The registry cell of the network is not a cell dep";
+  ActionMissing = 44, Action, "No action is declared in the witness of the cell";
+  NFTCostOverflow = 45, Payment, "The cost or the occupied capacity of the minted nfts overflows";
+}
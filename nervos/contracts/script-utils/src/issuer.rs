@@ -1,5 +1,6 @@
 use crate::error::Error;
-use crate::helper::{parse_dyn_vec_len, u32_from_slice, DYN_MIN_LEN};
+use crate::helper::{parse_dyn_vec_len, to_dyn_vec, u32_from_slice, DYN_MIN_LEN, LOCK_HASH_LEN};
+use alloc::vec::Vec;
 use core::result::Result;
 
 const FIXED_LEN: usize = 5;
@@ -7,14 +8,26 @@ const FIXED_LEN: usize = 5;
 const ISSUER_DATA_MIN_LEN: usize = 7;
 pub const ISSUER_TYPE_ARGS_LEN: usize = 20;
 
+pub const ISSUER_FLAG_OWNER_LOCK_HASH: u8 = 0b0000_0001;
+
 /// Issuer cell data structure
 /// This structure contains the following information:
 /// 1) version: u8
 /// 2) class_count: u32
+/// 3) name: <size: u16> + <content>
+/// 4) flags: u8
+/// 5) owner_lock_hash: [u8; 32]
+/// The field of 4) can be missing. The field of 5) exists only if the owner lock hash
+/// flag is set, an input locked by it then authorizes spending the cell instead of a
+/// witness lock. Any other data after the name, such as the data of issuers created
+/// before the flags, is ignored.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Issuer {
   pub version: u8,
   pub class_count: u32,
+  pub flags: u8,
+  pub owner_lock_hash: Option<[u8; LOCK_HASH_LEN]>,
 }
 
 impl Issuer {
@@ -35,9 +48,44 @@ impl Issuer {
       return Err(Error::IssuerDataInvalid);
     }
 
+    let flags_index = FIXED_LEN + name_len;
+    let flags = data.get(flags_index).copied().unwrap_or(0);
+
+    let owner_index = flags_index + 1;
+    let has_owner_lock_hash = flags & ISSUER_FLAG_OWNER_LOCK_HASH == ISSUER_FLAG_OWNER_LOCK_HASH;
+    let owner_lock_hash = if has_owner_lock_hash {
+      if data.len() < owner_index + LOCK_HASH_LEN {
+        return Err(Error::IssuerDataInvalid);
+      }
+      let mut owner_lock_hash = [0u8; LOCK_HASH_LEN];
+      owner_lock_hash.copy_from_slice(&data[owner_index..(owner_index + LOCK_HASH_LEN)]);
+      Some(owner_lock_hash)
+    } else {
+      None
+    };
+
     Ok(Issuer {
       version,
       class_count,
+      flags,
+      owner_lock_hash,
     })
   }
+
+  /// Encodes the issuer, the name is not kept by from_data so it is passed here. Flags are
+  /// only written when set, the owner lock hash is written when present and flags must be
+  /// set accordingly.
+  pub fn to_data(&self, name: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.push(self.version);
+    data.extend_from_slice(&self.class_count.to_be_bytes());
+    data.extend(to_dyn_vec(name));
+    if self.flags != 0 {
+      data.push(self.flags);
+    }
+    if let Some(owner_lock_hash) = &self.owner_lock_hash {
+      data.extend_from_slice(owner_lock_hash);
+    }
+    data
+  }
 }
@@ -1,9 +1,24 @@
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 extern crate alloc;
 
+pub mod action;
+pub mod attribute;
+#[cfg(feature = "contract")]
+pub mod auth;
+#[cfg(feature = "contract")]
+pub mod cells;
 pub mod class;
+pub mod codes;
 pub mod error;
 pub mod helper;
 pub mod issuer;
 pub mod nft;
 pub mod metadata;
+pub mod network;
+pub mod reader;
+pub mod registry;
+#[cfg(feature = "contract")]
+pub mod trace;
+#[cfg(feature = "contract")]
+pub mod tx_index;
+pub mod type_id;
@@ -1,47 +1,321 @@
+use crate::attribute::AttributeSchema;
 use crate::error::Error;
-use crate::helper::{parse_dyn_vec_len, DYN_MIN_LEN};
+use crate::helper::{to_dyn_vec, DYN_MIN_LEN, DYN_U32_MIN_LEN, LOCK_HASH_LEN};
+use crate::reader::DataReader;
+use core::ops::Range;
 use core::result::Result;
 use alloc::vec::Vec;
+#[cfg(feature = "contract")]
+use {
+  crate::reader::{data_equal, hash_range, range_equal, CellDataReader},
+  ckb_std::ckb_constants::Source,
+};
 
 const FIXED_LEN: usize = 0;
-// FIXED_LEN + DYN_MIN_LEN
-const METADATA_DATA_MIN_LEN: usize = 4;
+// FIXED_LEN + DYN_MIN_LEN + DYN_U32_MIN_LEN
+const METADATA_DATA_MIN_LEN: usize = 6;
 pub const METADATA_TYPE_ARGS_LEN: usize = 20;
 
+const FLAGS_LEN: usize = 1;
+// revision(u32) + prev_data_hash
+const HISTORY_LEN: usize = 36;
+// index(u16) + count(u16) + document_id
+const CHUNK_LEN: usize = 24;
+pub const PREV_DATA_HASH_LEN: usize = 32;
+
+pub const METADATA_FLAG_HISTORY: u8 = 0b0000_0001;
+pub const METADATA_FLAG_CHUNKED: u8 = 0b0000_0010;
+pub const METADATA_FLAG_ATTRIBUTES: u8 = 0b0000_0100;
+pub const METADATA_FLAG_SCHEMA: u8 = 0b0000_1000;
+pub const METADATA_FLAG_FROZEN: u8 = 0b0001_0000;
+pub const METADATA_FLAG_OWNER_LOCK_HASH: u8 = 0b0010_0000;
+
 /// Metadata cell data structure
 /// This structure contains the following information:
 /// 1) name: <size: u16> + <content>
 /// 2) data: <size: u32> + <content>
+/// 3) flags: u8
+/// 4) history: <revision: u32> + <prev_data_hash: [u8; 32]>
+/// 5) chunk: <index: u16> + <count: u16> + <document_id: [u8; 20]>
+/// 6) schema: <size: u16> + <content>
+/// 7) owner_lock_hash: [u8; 32]
+/// The field of 3) can be missing and it is treated as no flag set.
+/// If the attributes flag is set, the content of 2) must decode as attribute records.
+/// If the frozen flag is set, the cell data cannot be changed and the cell cannot be destroyed.
+/// The field of 4) exists only if the history flag is set, prev_data_hash is the
+/// blake2b hash of the previous metadata cell data.
+/// The field of 5) exists only if the chunked flag is set. A chunked document spans
+/// `count` metadata cells, document_id is the type args of the chunk with index 0. The
+/// chunk cannot be changed after the cell is created.
+/// The field of 6) exists only if the schema flag is set, it is the attribute schema
+/// that NFTs of the classes linked to this metadata cell must satisfy at mint. The schema
+/// cannot be added, changed or removed after the cell is created.
+/// The field of 7) exists only if the owner lock hash flag is set, an input locked by it
+/// then authorizes spending the cell as well as a witness lock.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Metadata {
   pub name: Vec<u8>,
   pub data: Vec<u8>,
+  pub flags: u8,
+  pub history: Option<MetadataHistory>,
+  pub chunk: Option<MetadataChunk>,
+  pub schema: Option<AttributeSchema>,
+  pub owner_lock_hash: Option<[u8; LOCK_HASH_LEN]>,
 }
 
-impl Metadata {
-  pub fn from_data(data_raw: &[u8]) -> Result<Self, Error> {
-    if data_raw.len() < METADATA_DATA_MIN_LEN {
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataHistory {
+  pub revision: u32,
+  pub prev_data_hash: [u8; PREV_DATA_HASH_LEN],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetadataChunk {
+  pub index: u16,
+  pub count: u16,
+  pub document_id: [u8; METADATA_TYPE_ARGS_LEN],
+}
+
+/// Offsets of the metadata fields, found from their size prefixes without copying the
+/// fields. The name and data ranges include their size prefixes.
+#[derive(Debug, Clone)]
+pub struct MetadataLayout {
+  pub name: Range<usize>,
+  pub data: Range<usize>,
+  pub flags: u8,
+  pub history: Option<MetadataHistory>,
+  pub chunk: Option<MetadataChunk>,
+  pub schema: Option<Range<usize>>,
+  pub owner_lock_hash: Option<[u8; LOCK_HASH_LEN]>,
+}
+
+impl MetadataLayout {
+  pub fn parse<R: DataReader + ?Sized>(data_raw: &R) -> Result<Self, Error> {
+    let data_raw_len = data_raw.len();
+    if data_raw_len < METADATA_DATA_MIN_LEN {
       return Err(Error::MetadataDataInvalid);
     }
 
-    let name_len = parse_dyn_vec_len(&data_raw[FIXED_LEN..(FIXED_LEN + DYN_MIN_LEN)]);
-    // DYN_MIN_LEN: the min length of data
-    if data_raw.len() < FIXED_LEN + name_len + DYN_MIN_LEN {
+    let name_len = data_raw.read_dyn_len(FIXED_LEN)?;
+    // DYN_U32_MIN_LEN: the min length of data
+    if data_raw_len < FIXED_LEN + name_len + DYN_U32_MIN_LEN {
       return Err(Error::MetadataDataInvalid);
     }
-    let name = data_raw[FIXED_LEN..(FIXED_LEN + name_len)].to_vec();
+    let name = FIXED_LEN..(FIXED_LEN + name_len);
 
-    let data_index = FIXED_LEN + name_len;
-    let data_len =
-      parse_dyn_vec_len(&data_raw[data_index..(data_index + DYN_MIN_LEN)]);
-    let data = data_raw[data_index..(data_index + data_len)].to_vec();
+    let data_index = name.end;
+    let data_len = data_raw.read_dyn_u32_len(data_index)?;
+    if data_raw_len < data_index + data_len {
+      return Err(Error::MetadataDataInvalid);
+    }
+    let data = data_index..(data_index + data_len);
 
-    Ok(Metadata {
+    let flags_index = data.end;
+    let flags = if data_raw_len > flags_index { data_raw.read_u8(flags_index)? } else { 0 };
+
+    let history_index = flags_index + FLAGS_LEN;
+    let history = if flags & METADATA_FLAG_HISTORY == METADATA_FLAG_HISTORY {
+      if data_raw_len < history_index + HISTORY_LEN {
+        return Err(Error::MetadataDataInvalid);
+      }
+      let revision = data_raw.read_u32(history_index)?;
+      let mut prev_data_hash = [0u8; PREV_DATA_HASH_LEN];
+      data_raw.read(history_index + 4, &mut prev_data_hash)?;
+      Some(MetadataHistory {
+        revision,
+        prev_data_hash,
+      })
+    } else {
+      None
+    };
+
+    let chunk_index = history_index + history.as_ref().map_or(0, |_| HISTORY_LEN);
+    let chunk = if flags & METADATA_FLAG_CHUNKED == METADATA_FLAG_CHUNKED {
+      if data_raw_len < chunk_index + CHUNK_LEN {
+        return Err(Error::MetadataDataInvalid);
+      }
+      let index = data_raw.read_u16(chunk_index)?;
+      let count = data_raw.read_u16(chunk_index + 2)?;
+      if index >= count {
+        return Err(Error::MetadataChunkInvalid);
+      }
+      let mut document_id = [0u8; METADATA_TYPE_ARGS_LEN];
+      data_raw.read(chunk_index + 4, &mut document_id)?;
+      Some(MetadataChunk {
+        index,
+        count,
+        document_id,
+      })
+    } else {
+      None
+    };
+
+    let schema_index = chunk_index + chunk.as_ref().map_or(0, |_| CHUNK_LEN);
+    let schema = if flags & METADATA_FLAG_SCHEMA == METADATA_FLAG_SCHEMA {
+      if data_raw_len < schema_index + DYN_MIN_LEN {
+        return Err(Error::MetadataDataInvalid);
+      }
+      let schema_len = data_raw.read_dyn_len(schema_index)?;
+      if data_raw_len < schema_index + schema_len {
+        return Err(Error::MetadataDataInvalid);
+      }
+      Some((schema_index + DYN_MIN_LEN)..(schema_index + schema_len))
+    } else {
+      None
+    };
+
+    let owner_index = schema.as_ref().map_or(schema_index, |schema| schema.end);
+    let has_owner_lock_hash = flags & METADATA_FLAG_OWNER_LOCK_HASH == METADATA_FLAG_OWNER_LOCK_HASH;
+    let owner_lock_hash = if has_owner_lock_hash {
+      if data_raw_len < owner_index + LOCK_HASH_LEN {
+        return Err(Error::MetadataDataInvalid);
+      }
+      let mut owner_lock_hash = [0u8; LOCK_HASH_LEN];
+      data_raw.read(owner_index, &mut owner_lock_hash)?;
+      Some(owner_lock_hash)
+    } else {
+      None
+    };
+
+    Ok(MetadataLayout {
       name,
       data,
+      flags,
+      history,
+      chunk,
+      schema,
+      owner_lock_hash,
+    })
+  }
+
+  pub fn is_frozen(&self) -> bool {
+    self.flags & METADATA_FLAG_FROZEN == METADATA_FLAG_FROZEN
+  }
+}
+
+impl Metadata {
+  pub fn from_data(data_raw: &[u8]) -> Result<Self, Error> {
+    let layout = MetadataLayout::parse(data_raw)?;
+    let schema = match layout.schema {
+      Some(schema) => {
+        Some(AttributeSchema::from_data(&data_raw[schema]).map_err(|_| Error::MetadataDataInvalid)?)
+      }
+      None => None,
+    };
+    Ok(Metadata {
+      name: data_raw[layout.name].to_vec(),
+      data: data_raw[layout.data].to_vec(),
+      flags: layout.flags,
+      history: layout.history,
+      chunk: layout.chunk,
+      schema,
+      owner_lock_hash: layout.owner_lock_hash,
     })
   }
+
+  pub fn is_frozen(&self) -> bool {
+    self.flags & METADATA_FLAG_FROZEN == METADATA_FLAG_FROZEN
+  }
+
+  pub fn data_content(&self) -> &[u8] {
+    &self.data[DYN_U32_MIN_LEN..]
+  }
+
+  /// Encodes the metadata, name and data keep their size prefixes. The history, chunk,
+  /// schema and owner lock hash are written when present, flags must be set accordingly.
+  pub fn to_data(&self) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&self.name);
+    data.extend_from_slice(&self.data);
+    if self.flags == 0 {
+      return data;
+    }
+    data.push(self.flags);
+    if let Some(history) = &self.history {
+      data.extend_from_slice(&history.revision.to_be_bytes());
+      data.extend_from_slice(&history.prev_data_hash);
+    }
+    if let Some(chunk) = &self.chunk {
+      data.extend_from_slice(&chunk.index.to_be_bytes());
+      data.extend_from_slice(&chunk.count.to_be_bytes());
+      data.extend_from_slice(&chunk.document_id);
+    }
+    if let Some(schema) = &self.schema {
+      data.extend(to_dyn_vec(&schema.to_data()));
+    }
+    if let Some(owner_lock_hash) = &self.owner_lock_hash {
+      data.extend_from_slice(owner_lock_hash);
+    }
+    data
+  }
+
   pub fn immutable_equal(&self, other: &Metadata) -> bool {
+    let schema_data = |metadata: &Metadata| metadata.schema.as_ref().map(AttributeSchema::to_data);
     self.name == other.name
+      && self.chunk == other.chunk
+      && schema_data(self) == schema_data(other)
+  }
+}
+
+/// Metadata cell read in place, only its size prefixes and small fields are loaded so
+/// that large data is hashed and compared without copying it
+#[cfg(feature = "contract")]
+pub struct MetadataReader {
+  pub cell: CellDataReader,
+  pub layout: MetadataLayout,
+}
+
+#[cfg(feature = "contract")]
+impl MetadataReader {
+  pub fn load(index: usize, source: Source) -> Result<Self, Error> {
+    let cell = CellDataReader::load(index, source).map_err(|_| Error::MetadataDataInvalid)?;
+    let layout = MetadataLayout::parse(&cell)?;
+    Ok(MetadataReader { cell, layout })
+  }
+
+  pub fn is_frozen(&self) -> bool {
+    self.layout.is_frozen()
+  }
+
+  /// Blake2b hash of the whole cell data
+  pub fn data_hash(&self) -> Result<[u8; PREV_DATA_HASH_LEN], Error> {
+    hash_range(&self.cell, 0..self.cell.len())
+  }
+
+  /// Whether the whole cell data is equal to the cell data of other
+  pub fn cell_data_equal(&self, other: &MetadataReader) -> Result<bool, Error> {
+    data_equal(&self.cell, &other.cell)
+  }
+
+  /// Whether the data field is equal to the data field of other
+  pub fn data_equal(&self, other: &MetadataReader) -> Result<bool, Error> {
+    range_equal(&self.cell, self.layout.data.clone(), &other.cell, other.layout.data.clone())
+  }
+
+  /// Content of the data field without its size, loaded for the fields parsed from it
+  pub fn load_data_content(&self) -> Result<Vec<u8>, Error> {
+    let mut content = Vec::new();
+    content.resize(self.layout.data.len() - DYN_U32_MIN_LEN, 0);
+    self.cell.read(self.layout.data.start + DYN_U32_MIN_LEN, &mut content)?;
+    Ok(content)
+  }
+
+  pub fn immutable_equal(&self, other: &MetadataReader) -> Result<bool, Error> {
+    let (layout, other_layout) = (&self.layout, &other.layout);
+    let schema_equal = match (&layout.schema, &other_layout.schema) {
+      (Some(range), Some(other_range)) => {
+        range_equal(&self.cell, range.clone(), &other.cell, other_range.clone())?
+      }
+      (None, None) => true,
+      _ => false,
+    };
+    Ok(
+      range_equal(&self.cell, layout.name.clone(), &other.cell, other_layout.name.clone())?
+        && layout.chunk == other_layout.chunk
+        && schema_equal,
+    )
   }
 }
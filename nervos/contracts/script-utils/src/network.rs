@@ -0,0 +1,13 @@
+//! Deployment constants of each network. The testnet (Aggron) profile is used unless
+//! the `mainnet` or `devnet` feature is enabled.
+//!
+//! The type id of the registry cell is generated by `build.rs` from the output of
+//! `capsule deploy` for the network, off-chain crates use the values of `script-utils`
+//! built with the same features. The class type and the payment lock are read from the
+//! registry cell, see `registry.rs`, so no other constant is written by hand.
+
+// REGISTRY_TYPE_ID, the type id of the registry cell of the network
+include!(concat!(env!("OUT_DIR"), "/deployment.rs"));
+
+#[cfg(all(feature = "mainnet", feature = "devnet"))]
+compile_error!("only one of the `mainnet` and `devnet` features can be enabled");
@@ -12,6 +12,7 @@ pub const NFT_TYPE_ARGS_LEN: usize = 56;
 /// 1) version: u8
 /// 2) data: <size: u16> + <vartext>
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Nft {
   pub version: u8,
   pub data: Vec<u8>,
@@ -29,8 +30,23 @@ impl Nft {
     }
 
     let data_len = parse_dyn_vec_len(&raw_data[FIXED_LEN..(FIXED_LEN + DYN_MIN_LEN)]);
+    if raw_data.len() < FIXED_LEN + data_len {
+      return Err(Error::NFTDataInvalid);
+    }
     let data = raw_data[FIXED_LEN..(FIXED_LEN + data_len)].to_vec();
 
     Ok(Nft { version, data })
   }
+
+  pub fn data_content(&self) -> &[u8] {
+    &self.data[DYN_MIN_LEN..]
+  }
+
+  /// Encodes the NFT, data keeps its size prefix
+  pub fn to_data(&self) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.push(self.version);
+    data.extend_from_slice(&self.data);
+    data
+  }
 }
@@ -0,0 +1,158 @@
+use crate::error::Error;
+use crate::helper::{DYN_MIN_LEN, DYN_U32_MIN_LEN};
+use crate::type_id::new_blake2b;
+use core::ops::Range;
+use core::result::Result;
+#[cfg(feature = "contract")]
+use ckb_std::{ckb_constants::Source, error::SysError, syscalls};
+
+// the size of the buffers used to hash and compare data in place
+const STREAM_CHUNK_LEN: usize = 256;
+
+/// Random access to cell data, either loaded in memory or read in place from a cell,
+/// so that parsers can find the fields from their size prefixes without copying them
+pub trait DataReader {
+  fn len(&self) -> usize;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Fills buf with the data at offset, the whole range must be within the data
+  fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Error>;
+
+  fn read_u8(&self, offset: usize) -> Result<u8, Error> {
+    let mut buf = [0u8; 1];
+    self.read(offset, &mut buf)?;
+    Ok(buf[0])
+  }
+
+  fn read_u16(&self, offset: usize) -> Result<u16, Error> {
+    let mut buf = [0u8; 2];
+    self.read(offset, &mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+  }
+
+  fn read_u32(&self, offset: usize) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    self.read(offset, &mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+  }
+
+  fn read_u64(&self, offset: usize) -> Result<u64, Error> {
+    let mut buf = [0u8; 8];
+    self.read(offset, &mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+  }
+
+  /// Length of the dynamic field at offset, including its u16 size
+  fn read_dyn_len(&self, offset: usize) -> Result<usize, Error> {
+    Ok(self.read_u16(offset)? as usize + DYN_MIN_LEN)
+  }
+
+  /// Length of the large dynamic field at offset, including its u32 size
+  fn read_dyn_u32_len(&self, offset: usize) -> Result<usize, Error> {
+    Ok(self.read_u32(offset)? as usize + DYN_U32_MIN_LEN)
+  }
+}
+
+impl DataReader for [u8] {
+  fn len(&self) -> usize {
+    <[u8]>::len(self)
+  }
+
+  fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Error> {
+    let end = offset.checked_add(buf.len()).ok_or(Error::LengthNotEnough)?;
+    let data = self.get(offset..end).ok_or(Error::LengthNotEnough)?;
+    buf.copy_from_slice(data);
+    Ok(())
+  }
+}
+
+/// Data of a cell read in place with the offset-based load_cell_data syscall, only the
+/// requested ranges are loaded
+#[cfg(feature = "contract")]
+pub struct CellDataReader {
+  index: usize,
+  source: Source,
+  len: usize,
+}
+
+#[cfg(feature = "contract")]
+impl CellDataReader {
+  pub fn load(index: usize, source: Source) -> Result<Self, Error> {
+    // An empty buffer only returns the length of the data
+    let len = match syscalls::load_cell_data(&mut [], 0, index, source) {
+      Ok(len) => len,
+      Err(SysError::LengthNotEnough(len)) => len,
+      Err(err) => return Err(err.into()),
+    };
+    Ok(CellDataReader { index, source, len })
+  }
+}
+
+#[cfg(feature = "contract")]
+impl DataReader for CellDataReader {
+  fn len(&self) -> usize {
+    self.len
+  }
+
+  fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), Error> {
+    if offset.checked_add(buf.len()).map_or(true, |end| end > self.len) {
+      return Err(Error::LengthNotEnough);
+    }
+    match syscalls::load_cell_data(buf, offset, self.index, self.source) {
+      Ok(_) | Err(SysError::LengthNotEnough(_)) => Ok(()),
+      Err(err) => Err(err.into()),
+    }
+  }
+}
+
+/// Blake2b hash of a range of the data, read in chunks
+pub fn hash_range<R: DataReader + ?Sized>(data: &R, range: Range<usize>) -> Result<[u8; 32], Error> {
+  let mut blake2b = new_blake2b();
+  let mut buf = [0u8; STREAM_CHUNK_LEN];
+  let mut offset = range.start;
+  while offset < range.end {
+    let chunk_len = core::cmp::min(STREAM_CHUNK_LEN, range.end - offset);
+    data.read(offset, &mut buf[..chunk_len])?;
+    blake2b.update(&buf[..chunk_len]);
+    offset += chunk_len;
+  }
+  let mut hash = [0u8; 32];
+  blake2b.finalize(&mut hash);
+  Ok(hash)
+}
+
+/// Whether two ranges hold the same bytes, compared chunk by chunk
+pub fn range_equal<A: DataReader + ?Sized, B: DataReader + ?Sized>(
+  data: &A,
+  range: Range<usize>,
+  other: &B,
+  other_range: Range<usize>,
+) -> Result<bool, Error> {
+  if range.len() != other_range.len() {
+    return Ok(false);
+  }
+  let mut buf = [0u8; STREAM_CHUNK_LEN];
+  let mut other_buf = [0u8; STREAM_CHUNK_LEN];
+  let mut offset = 0;
+  while offset < range.len() {
+    let chunk_len = core::cmp::min(STREAM_CHUNK_LEN, range.len() - offset);
+    data.read(range.start + offset, &mut buf[..chunk_len])?;
+    other.read(other_range.start + offset, &mut other_buf[..chunk_len])?;
+    if buf[..chunk_len] != other_buf[..chunk_len] {
+      return Ok(false);
+    }
+    offset += chunk_len;
+  }
+  Ok(true)
+}
+
+/// Whether both readers hold the same data
+pub fn data_equal<A: DataReader + ?Sized, B: DataReader + ?Sized>(
+  data: &A,
+  other: &B,
+) -> Result<bool, Error> {
+  range_equal(data, 0..data.len(), other, 0..other.len())
+}
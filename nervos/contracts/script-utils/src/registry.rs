@@ -0,0 +1,148 @@
+use crate::error::Error;
+use crate::helper::{parse_dyn_vec_len, HashType, DYN_MIN_LEN};
+use alloc::vec::Vec;
+use core::result::Result;
+#[cfg(feature = "contract")]
+use {
+  crate::helper::{hash_types_match, load_cell_data_by_type},
+  crate::network::REGISTRY_TYPE_ID,
+  crate::type_id::TYPE_ID_CODE_HASH,
+  ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{bytes::Bytes, packed::*, prelude::*},
+  },
+};
+
+const CODE_HASH_LEN: usize = 32;
+// code_hash + hash_type(u8)
+const SCRIPT_REF_LEN: usize = 33;
+// version(u8) + SCRIPT_REF_LEN * 3 + DYN_MIN_LEN
+const REGISTRY_DATA_MIN_LEN: usize = 102;
+#[cfg(feature = "contract")]
+const TYPE: u8 = 1;
+
+/// Reference to a deployed script, args are only set for complete scripts such as the
+/// payment lock
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScriptRef {
+  pub code_hash: [u8; CODE_HASH_LEN],
+  pub hash_type: u8,
+  pub args: Vec<u8>,
+}
+
+impl ScriptRef {
+  fn from_slice(data: &[u8]) -> Result<Self, Error> {
+    let mut code_hash = [0u8; CODE_HASH_LEN];
+    code_hash.copy_from_slice(&data[0..CODE_HASH_LEN]);
+    let hash_type = data[CODE_HASH_LEN];
+    HashType::from_u8(hash_type).map_err(|_| Error::RegistryDataInvalid)?;
+    Ok(ScriptRef {
+      code_hash,
+      hash_type,
+      args: Vec::new(),
+    })
+  }
+}
+
+#[cfg(feature = "contract")]
+impl ScriptRef {
+  pub fn build(&self, args: &[u8]) -> Script {
+    Script::new_builder()
+      .code_hash(self.code_hash.pack())
+      .args(args.pack())
+      .hash_type(Byte::new(self.hash_type))
+      .build()
+  }
+
+  pub fn build_with_args(&self) -> Script {
+    self.build(&self.args)
+  }
+
+  /// Whether script runs the referenced code, regardless of its args. A script
+  /// referencing the code by data, data1 or data2 matches any of the data hash types.
+  pub fn matches(&self, script: &Script) -> bool {
+    script.code_hash().as_slice() == &self.code_hash[..]
+      && hash_types_match(self.hash_type, script.hash_type().as_slice()[0])
+  }
+
+  /// Whether script runs the referenced code with the referenced args
+  pub fn matches_with_args(&self, script: &Script) -> bool {
+    let args: Bytes = script.args().unpack();
+    self.matches(script) && args[..] == self.args[..]
+  }
+}
+
+/// Registry cell data structure
+/// This structure contains the following information:
+/// 1) version: u8
+/// 2) class_type: <code_hash: [u8; 32]> + <hash_type: u8>, hash_type is data, type,
+///    data1 or data2 in every reference
+/// 3) issuer_type: <code_hash: [u8; 32]> + <hash_type: u8>
+/// 4) payment_lock: <code_hash: [u8; 32]> + <hash_type: u8>
+/// 5) payment_lock_args: <size: u16> + <content>
+/// The registry cell is a cell dep whose type script is the Type ID script with
+/// REGISTRY_TYPE_ID of the network as args, so only its owner can update it. Every
+/// transaction run by a contract which resolves scripts from the registry must reference
+/// it, there are no network defaults to fall back to.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Registry {
+  pub version: u8,
+  pub class_type: ScriptRef,
+  pub issuer_type: ScriptRef,
+  pub payment_lock: ScriptRef,
+}
+
+impl Registry {
+  pub fn from_data(data: &[u8]) -> Result<Self, Error> {
+    if data.len() < REGISTRY_DATA_MIN_LEN {
+      return Err(Error::RegistryDataInvalid);
+    }
+
+    let version: u8 = data[0];
+    if version != 0 {
+      return Err(Error::VersionInvalid);
+    }
+
+    let class_index = 1;
+    let issuer_index = class_index + SCRIPT_REF_LEN;
+    let payment_index = issuer_index + SCRIPT_REF_LEN;
+    let class_type = ScriptRef::from_slice(&data[class_index..issuer_index])?;
+    let issuer_type = ScriptRef::from_slice(&data[issuer_index..payment_index])?;
+    let mut payment_lock =
+      ScriptRef::from_slice(&data[payment_index..(payment_index + SCRIPT_REF_LEN)])?;
+
+    let payment_args_index = payment_index + SCRIPT_REF_LEN;
+    let payment_args_len =
+      parse_dyn_vec_len(&data[payment_args_index..(payment_args_index + DYN_MIN_LEN)]);
+    if data.len() < payment_args_index + payment_args_len {
+      return Err(Error::RegistryDataInvalid);
+    }
+    payment_lock.args =
+      data[(payment_args_index + DYN_MIN_LEN)..(payment_args_index + payment_args_len)].to_vec();
+
+    Ok(Registry {
+      version,
+      class_type,
+      issuer_type,
+      payment_lock,
+    })
+  }
+}
+
+/// Loads the registry from the cell deps, the registry cell of the network must be there
+#[cfg(feature = "contract")]
+pub fn load_registry() -> Result<Registry, Error> {
+  let registry_type = Script::new_builder()
+    .code_hash(TYPE_ID_CODE_HASH.pack())
+    .args(REGISTRY_TYPE_ID[..].pack())
+    .hash_type(Byte::new(TYPE))
+    .build();
+  match load_cell_data_by_type(Source::CellDep, &|type_: &Script| {
+    type_.as_slice() == registry_type.as_slice()
+  }) {
+    Some(data) => Registry::from_data(&data),
+    None => Err(Error::RegistryCellMissing),
+  }
+}
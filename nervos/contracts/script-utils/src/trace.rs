@@ -0,0 +1,34 @@
+/// Emits a structured debug event such as `cdm event=payment paid=100 required=200`.
+/// Events are only compiled in with the `trace` feature of the calling contract, builds
+/// without it drop them with their arguments. They are printed through the debug syscall
+/// directly, so release builds with the feature print them too.
+#[macro_export]
+macro_rules! trace {
+  ($event:literal $(, $key:ident = $value:expr)* $(,)?) => {
+    #[cfg(feature = "trace")]
+    {
+      let event = alloc::format!(
+        concat!("cdm event=", $event $(, " ", stringify!($key), "={:?}")*)
+        $(, $value)*
+      );
+      ckb_std::syscalls::debug(event);
+    }
+  };
+}
+
+/// Evaluates to the error after emitting a `rejected` event with its id and the place it
+/// is returned from, such as `return Err(rejected!(Error::PaymentNotEnough))`
+#[macro_export]
+macro_rules! rejected {
+  ($error:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+    let error = $error;
+    $crate::trace!(
+      "rejected",
+      error = error,
+      id = error.id(),
+      site = concat!(file!(), ":", line!())
+      $(, $key = $value)*
+    );
+    error
+  }};
+}
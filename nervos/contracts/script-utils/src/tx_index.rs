@@ -0,0 +1,105 @@
+use crate::error::Error;
+use alloc::vec::Vec;
+use ckb_std::{
+  ckb_constants::Source,
+  ckb_types::{packed::*, prelude::*},
+  high_level::{load_cell, load_cell_data, load_cell_occupied_capacity, QueryIter},
+};
+use core::result::Result;
+
+/// Type, lock and capacity of a cell, loaded with a single syscall. Data is loaded on
+/// demand.
+pub struct IndexedCell {
+  pub source: Source,
+  pub index: usize,
+  pub type_: Option<Script>,
+  pub lock: Script,
+  pub capacity: u64,
+}
+
+impl IndexedCell {
+  fn new(source: Source, index: usize, output: CellOutput) -> Self {
+    IndexedCell {
+      source,
+      index,
+      type_: output.type_().to_opt(),
+      lock: output.lock(),
+      capacity: output.capacity().unpack(),
+    }
+  }
+
+  pub fn has_type(&self, predicate: &dyn Fn(&Script) -> bool) -> bool {
+    self.type_.as_ref().map_or(false, |type_| predicate(type_))
+  }
+
+  pub fn load_data(&self) -> Result<Vec<u8>, Error> {
+    Ok(load_cell_data(self.index, self.source)?)
+  }
+
+  pub fn load_occupied_capacity(&self) -> Result<u64, Error> {
+    Ok(load_cell_occupied_capacity(self.index, self.source)?)
+  }
+}
+
+/// Index of the inputs, outputs and cell deps of the transaction. Every cell is loaded
+/// once, so the lookups of a script do not scan the transaction again and their cost
+/// grows linearly with the number of cells.
+pub struct TxIndex {
+  pub inputs: Vec<IndexedCell>,
+  pub outputs: Vec<IndexedCell>,
+  pub cell_deps: Vec<IndexedCell>,
+}
+
+impl TxIndex {
+  pub fn load() -> Self {
+    let load_cells = |source| {
+      QueryIter::new(load_cell, source)
+        .enumerate()
+        .map(|(index, output)| IndexedCell::new(source, index, output))
+        .collect()
+    };
+    TxIndex {
+      inputs: load_cells(Source::Input),
+      outputs: load_cells(Source::Output),
+      cell_deps: load_cells(Source::CellDep),
+    }
+  }
+
+  /// Cells of the source, which is Input, Output or CellDep
+  pub fn cells(&self, source: Source) -> &[IndexedCell] {
+    match source {
+      Source::Input => &self.inputs,
+      Source::Output => &self.outputs,
+      Source::CellDep => &self.cell_deps,
+      _ => &[],
+    }
+  }
+
+  pub fn filter_by_type<'a>(
+    &'a self,
+    source: Source,
+    predicate: &'a dyn Fn(&Script) -> bool,
+  ) -> impl Iterator<Item = &'a IndexedCell> + 'a {
+    self.cells(source).iter().filter(move |cell| cell.has_type(predicate))
+  }
+
+  pub fn find_by_type(&self, source: Source, predicate: &dyn Fn(&Script) -> bool) -> Option<&IndexedCell> {
+    self.cells(source).iter().find(|cell| cell.has_type(predicate))
+  }
+
+  pub fn count_by_type(&self, source: Source, predicate: &dyn Fn(&Script) -> bool) -> usize {
+    self.filter_by_type(source, predicate).count()
+  }
+
+  /// Whether a cell of the source has exactly this type script
+  pub fn has_type(&self, source: Source, type_script: &Script) -> bool {
+    self
+      .cells(source)
+      .iter()
+      .any(|cell| cell.has_type(&|type_: &Script| type_.as_slice() == type_script.as_slice()))
+  }
+
+  pub fn find_by_lock(&self, source: Source, predicate: &dyn Fn(&Script) -> bool) -> Option<&IndexedCell> {
+    self.cells(source).iter().find(|cell| predicate(&cell.lock))
+  }
+}
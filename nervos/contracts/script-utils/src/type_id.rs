@@ -0,0 +1,68 @@
+use blake2b_rs::{Blake2b, Blake2bBuilder};
+#[cfg(feature = "contract")]
+use {
+  crate::error::Error,
+  crate::helper::load_output_index_by_type,
+  ckb_std::{
+    ckb_constants::Source,
+    ckb_types::{packed::*, prelude::*},
+    high_level::load_input,
+  },
+  core::result::Result,
+};
+
+pub const TYPE_ID_LEN: usize = 32;
+/// Code hash of the built-in Type ID script, "TYPE_ID" in ascii
+pub const TYPE_ID_CODE_HASH: [u8; 32] = [
+  0, 0, 0, 0, 0, 0, 0, 0,
+  0, 0, 0, 0, 0, 0, 0, 0,
+  0, 0, 0, 0, 0, 0, 0, 0,
+  0, 0x54, 0x59, 0x50, 0x45, 0x5f, 0x49, 0x44
+];
+const BLAKE2B_LEN: usize = 32;
+const CKB_HASH_PERSONALIZATION: &[u8] = b"ckb-default-hash";
+
+pub fn new_blake2b() -> Blake2b {
+  Blake2bBuilder::new(BLAKE2B_LEN)
+    .personal(CKB_HASH_PERSONALIZATION)
+    .build()
+}
+
+pub fn blake2b_256(data: &[u8]) -> [u8; BLAKE2B_LEN] {
+  let mut blake2b = new_blake2b();
+  blake2b.update(data);
+  let mut ret = [0; BLAKE2B_LEN];
+  blake2b.finalize(&mut ret);
+  ret
+}
+
+/// Type ID of the cell created at output_index as defined by the CKB Type ID spec:
+/// blake2b(first CellInput of the transaction || output_index as u64 little endian),
+/// first_input is the molecule encoded CellInput
+pub fn calc_type_id(first_input: &[u8], output_index: u64) -> [u8; TYPE_ID_LEN] {
+  let mut blake2b = new_blake2b();
+  blake2b.update(first_input);
+  blake2b.update(&output_index.to_le_bytes());
+  let mut ret = [0; TYPE_ID_LEN];
+  blake2b.finalize(&mut ret);
+  ret
+}
+
+/// Loads the type id of the output whose type script is type_script
+#[cfg(feature = "contract")]
+pub fn load_type_id(type_script: &Script) -> Result<[u8; TYPE_ID_LEN], Error> {
+  let first_input = load_input(0, Source::Input)?;
+  let output_index = load_output_index_by_type(type_script).ok_or(Error::TypeArgsInvalid)?;
+  Ok(calc_type_id(first_input.as_slice(), output_index as u64))
+}
+
+/// Checks that id, which may be a prefix of the type id, matches the type id of the
+/// output whose type script is type_script
+#[cfg(feature = "contract")]
+pub fn check_type_id(type_script: &Script, id: &[u8]) -> Result<(), Error> {
+  let type_id = load_type_id(type_script)?;
+  if id.is_empty() || id.len() > TYPE_ID_LEN || id[..] != type_id[0..id.len()] {
+    return Err(Error::TypeArgsInvalid);
+  }
+  Ok(())
+}
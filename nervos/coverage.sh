@@ -0,0 +1,21 @@
+#!/bin/sh
+# Line coverage of script-utils and the contract entries. The contracts are built as
+# native executables with their simulator feature and instrumented, then the tests run
+# them through ckb-x64-simulator. Requires grcov and the llvm-tools-preview component.
+set -e
+cd "$(dirname "$0")"
+
+rm -rf target/coverage
+mkdir -p target/coverage
+for contract in issuer-type class-type nft-type metadata; do
+  (cd "contracts/$contract" && \
+    RUSTFLAGS="-C instrument-coverage" cargo build --features simulator,placeholder-registry \
+      --target-dir ../../target/simulator)
+done
+
+LLVM_PROFILE_FILE="$PWD/target/coverage/%p-%m.profraw" CAPSULE_TEST_ENV=simulator \
+  cargo test -p tests
+
+grcov target/coverage --binary-path target/simulator/debug -s . -t html \
+  --ignore-not-existing --keep-only 'contracts/*' -o target/coverage/html
+echo "coverage report in target/coverage/html/index.html"
@@ -0,0 +1,193 @@
+//! Decodes the exit codes of the CDM contracts into named errors with an explanation,
+//! for wallets and logs.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[path = "../../contracts/script-utils/src/codes.rs"]
+mod codes;
+
+pub use codes::{Contract, Error, Namespace};
+
+impl FromStr for Contract {
+    type Err = UnknownContract;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "issuer-type" | "issuer" => Ok(Contract::IssuerType),
+            "class-type" | "class" => Ok(Contract::ClassType),
+            "nft-type" | "nft" => Ok(Contract::NftType),
+            "metadata" | "metadata-type" => Ok(Contract::Metadata),
+            _ => Err(UnknownContract(name.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownContract(pub String);
+
+impl fmt::Display for UnknownContract {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown CDM contract {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownContract {}
+
+/// Exit code of a contract, with the contract and the error it stands for when the code
+/// is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedError {
+    pub code: i8,
+    pub contract: Option<Contract>,
+    pub error: Option<Error>,
+}
+
+impl DecodedError {
+    /// Namespaced name of the error, such as `nft-type::payment::PaymentNotEnough`
+    pub fn name(&self) -> String {
+        match (self.contract, self.error) {
+            (Some(contract), Some(error)) => format!(
+                "{}::{}::{}",
+                contract.as_str(),
+                error.namespace().as_str(),
+                error.name()
+            ),
+            (Some(contract), None) => format!("{}::unknown::{}", contract.as_str(), self.code),
+            (None, _) => format!("unknown::{}", self.code),
+        }
+    }
+
+    pub fn explanation(&self) -> &'static str {
+        match self.error {
+            Some(error) => error.explanation(),
+            None => "The exit code is not a CDM error code, it may come from another script",
+        }
+    }
+}
+
+impl fmt::Display for DecodedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}): {}", self.name(), self.code, self.explanation())
+    }
+}
+
+/// Decodes an exit code, the range it falls in tells the contract which returned it
+pub fn decode(code: i8) -> DecodedError {
+    let decoded = Contract::decode(code);
+    DecodedError {
+        code,
+        contract: decoded.map(|(contract, _)| contract),
+        error: decoded.map(|(_, error)| error),
+    }
+}
+
+/// Decodes an exit code returned by the contract with this name, such as `nft-type`.
+/// Codes in the range of another contract are unknown, the code was attributed to the
+/// wrong script.
+pub fn decode_by_name(contract: &str, code: i8) -> Result<DecodedError, UnknownContract> {
+    let contract = contract.parse()?;
+    let decoded = decode(code);
+    if decoded.contract == Some(contract) {
+        Ok(decoded)
+    } else {
+        Ok(DecodedError {
+            code,
+            contract: Some(contract),
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_known_code() {
+        let decoded = decode(-22);
+        assert_eq!(decoded.contract, Some(Contract::NftType));
+        assert_eq!(decoded.error, Some(Error::PaymentNotEnough));
+        assert_eq!(decoded.name(), "nft-type::payment::PaymentNotEnough");
+        assert_eq!(
+            decoded.to_string(),
+            "nft-type::payment::PaymentNotEnough (-22): \
+             The payment output is smaller than the cost of the minted nfts"
+        );
+    }
+
+    #[test]
+    fn test_decode_contract_ranges() {
+        let error = Error::OwnerAuthorizationMissing;
+        let codes = [
+            (Contract::IssuerType, 41),
+            (Contract::ClassType, 104),
+            (Contract::NftType, -42),
+            (Contract::Metadata, -105),
+        ];
+        for (contract, code) in codes.iter() {
+            assert_eq!(contract.exit_code(error), *code);
+            let decoded = decode(*code);
+            assert_eq!(decoded.contract, Some(*contract));
+            assert_eq!(decoded.error, Some(error));
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_code() {
+        // 0 is success, -1 a panic, 46 to 63 and the matching codes of the other ranges
+        // are free ids and -128 is past the last range
+        for code in &[0, -1, 46, 63, 109, 126, -47, -64, -110, -127, -128] {
+            let decoded = decode(*code);
+            assert_eq!(decoded.error, None);
+            assert_eq!(decoded.name(), format!("unknown::{}", code));
+        }
+    }
+
+    #[test]
+    fn test_decode_by_name() {
+        let names = [
+            ("issuer-type", Contract::IssuerType),
+            ("issuer", Contract::IssuerType),
+            ("class-type", Contract::ClassType),
+            ("class", Contract::ClassType),
+            ("nft-type", Contract::NftType),
+            ("nft", Contract::NftType),
+            ("metadata", Contract::Metadata),
+            ("metadata-type", Contract::Metadata),
+        ];
+        for (name, contract) in names.iter() {
+            let code = contract.exit_code(Error::OwnerAuthorizationMissing);
+            let decoded = decode_by_name(name, code).expect("known contract");
+            assert_eq!(decoded.contract, Some(*contract));
+            assert_eq!(decoded.error, Some(Error::OwnerAuthorizationMissing));
+        }
+        assert_eq!(
+            decode_by_name("always-success", 1),
+            Err(UnknownContract("always-success".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_decode_by_name_of_other_contract() {
+        // A payment error of nft-type attributed to metadata
+        let decoded = decode_by_name("metadata", -22).expect("known contract");
+        assert_eq!(decoded.contract, Some(Contract::Metadata));
+        assert_eq!(decoded.error, None);
+        assert_eq!(decoded.name(), "metadata::unknown::-22");
+    }
+
+    #[test]
+    fn test_codes_round_trip() {
+        for contract in Contract::ALL {
+            for error in Error::ALL {
+                let code = contract.exit_code(*error);
+                assert_eq!(Contract::decode(code), Some((*contract, *error)));
+            }
+        }
+        // Ids are appended without gaps and fit the ranges
+        let max_id = Error::ALL.iter().map(Error::id).max().unwrap();
+        assert_eq!(Error::ALL.len(), max_id as usize);
+        assert!(max_id <= Error::MAX_ID);
+    }
+}
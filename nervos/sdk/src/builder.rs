@@ -0,0 +1,416 @@
+use crate::deployment::{ContractRef, Deployment};
+use crate::error::Error;
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use script_utils::{
+    action::CdmAction,
+    class::Class,
+    helper::{to_dyn_u32_vec, to_dyn_vec},
+    issuer::{Issuer, ISSUER_TYPE_ARGS_LEN},
+    metadata::Metadata,
+    nft::Nft,
+    type_id::{blake2b_256, calc_type_id},
+};
+use std::collections::{HashMap, HashSet};
+
+/// Length of the witness lock left for the secp256k1 signature
+pub const SIGNATURE_PLACEHOLDER_LEN: usize = 65;
+const SHANNONS_PER_CKB: u64 = 100_000_000;
+// class_count follows the version in the issuer data
+const ISSUER_CLASS_COUNT_OFFSET: usize = 1;
+
+/// Live cell spent or referenced by a transaction
+#[derive(Debug, Clone)]
+pub struct LiveCell {
+    pub out_point: OutPoint,
+    pub output: CellOutput,
+    pub data: Bytes,
+}
+
+impl LiveCell {
+    fn capacity(&self) -> u64 {
+        self.output.capacity().unpack()
+    }
+
+    fn input(&self) -> CellInput {
+        CellInput::new(self.out_point.clone(), 0)
+    }
+
+    fn type_matching(&self, contract: &ContractRef, kind: &'static str) -> Result<Script, Error> {
+        self.output
+            .type_()
+            .to_opt()
+            .filter(|type_| contract.matches(type_))
+            .ok_or(Error::CellInvalid(kind))
+    }
+}
+
+/// Cells paying for the outputs and the fee, in shannons. The remaining capacity goes
+/// to a change cell locked by change_lock.
+#[derive(Debug, Clone)]
+pub struct Funding {
+    pub cells: Vec<LiveCell>,
+    pub change_lock: Script,
+    pub fee: u64,
+}
+
+// Transaction under construction, the funding cells are its first inputs
+struct Draft {
+    inputs: Vec<LiveCell>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    cell_deps: Vec<CellDep>,
+    // input_type and output_type of the witness at each index
+    actions: HashMap<usize, (Option<Bytes>, Option<Bytes>)>,
+}
+
+impl Draft {
+    fn new(funding: &Funding, cell_deps: Vec<CellDep>) -> Result<Self, Error> {
+        if funding.cells.is_empty() {
+            return Err(Error::CellInvalid("no funding cell"));
+        }
+        Ok(Draft {
+            inputs: funding.cells.clone(),
+            outputs: Vec::new(),
+            cell_deps,
+            actions: HashMap::new(),
+        })
+    }
+
+    // Type id of the output at output_index, from the first funding cell
+    fn type_id(&self, output_index: usize) -> [u8; 32] {
+        calc_type_id(self.inputs[0].input().as_slice(), output_index as u64)
+    }
+
+    fn push_input(&mut self, cell: &LiveCell) -> usize {
+        self.inputs.push(cell.clone());
+        self.inputs.len() - 1
+    }
+
+    /// Pushes the output with its minimum capacity
+    fn push_output(&mut self, output: CellOutput, data: Bytes) -> Result<usize, Error> {
+        let capacity = min_capacity(&output, &data)?;
+        self.push_output_with_capacity(output, data, capacity)
+    }
+
+    fn push_output_with_capacity(
+        &mut self,
+        output: CellOutput,
+        data: Bytes,
+        capacity: u64,
+    ) -> Result<usize, Error> {
+        let output = output.as_builder().capacity(capacity.pack()).build();
+        self.outputs.push((output, data));
+        Ok(self.outputs.len() - 1)
+    }
+
+    fn declare_input(&mut self, index: usize, action: CdmAction) {
+        self.actions.entry(index).or_default().0 = Some(Bytes::from(action.to_vec()));
+    }
+
+    fn declare_output(&mut self, index: usize, action: CdmAction) {
+        self.actions.entry(index).or_default().1 = Some(Bytes::from(action.to_vec()));
+    }
+
+    /// Adds the change cell and the witnesses
+    fn finish(mut self, funding: &Funding) -> Result<TransactionView, Error> {
+        let available = checked_sum(self.inputs.iter().map(LiveCell::capacity))?;
+        let spent = checked_sum(
+            self.outputs
+                .iter()
+                .map(|(output, _)| output.capacity().unpack()),
+        )?;
+        let change_output = CellOutput::new_builder()
+            .lock(funding.change_lock.clone())
+            .build();
+        let change_capacity = min_capacity(&change_output, &Bytes::new())?;
+        let needed = checked_sum(vec![spent, funding.fee, change_capacity])?;
+        if available < needed {
+            return Err(Error::CapacityNotEnough { needed, available });
+        }
+        self.push_output_with_capacity(
+            change_output,
+            Bytes::new(),
+            available - spent - funding.fee,
+        )?;
+
+        let witnesses_len = self
+            .actions
+            .keys()
+            .map(|index| index + 1)
+            .chain(Some(self.inputs.len()))
+            .max()
+            .unwrap_or(0);
+        let mut signed_locks = HashSet::new();
+        let witnesses: Vec<Bytes> = (0..witnesses_len)
+            .map(|index| {
+                // The first input of every lock group carries the signature
+                let lock = self
+                    .inputs
+                    .get(index)
+                    .filter(|cell| signed_locks.insert(cell.output.lock().calc_script_hash()))
+                    .map(|_| Bytes::from(vec![0u8; SIGNATURE_PLACEHOLDER_LEN]));
+                let (input_type, output_type) = self.actions.remove(&index).unwrap_or_default();
+                WitnessArgs::new_builder()
+                    .lock(lock.pack())
+                    .input_type(input_type.pack())
+                    .output_type(output_type.pack())
+                    .build()
+                    .as_bytes()
+            })
+            .collect();
+
+        let (outputs, outputs_data): (Vec<CellOutput>, Vec<Bytes>) =
+            self.outputs.into_iter().unzip();
+        Ok(TransactionBuilder::default()
+            .inputs(self.inputs.iter().map(LiveCell::input))
+            .outputs(outputs)
+            .outputs_data(outputs_data.pack())
+            .cell_deps(self.cell_deps)
+            .witnesses(witnesses.pack())
+            .build())
+    }
+}
+
+fn min_capacity(output: &CellOutput, data: &Bytes) -> Result<u64, Error> {
+    let data_capacity = Capacity::bytes(data.len()).map_err(|_| Error::CapacityOverflow)?;
+    output
+        .occupied_capacity(data_capacity)
+        .map(|capacity| capacity.as_u64())
+        .map_err(|_| Error::CapacityOverflow)
+}
+
+fn checked_sum<I: IntoIterator<Item = u64>>(values: I) -> Result<u64, Error> {
+    values
+        .into_iter()
+        .try_fold(0u64, |sum, value| sum.checked_add(value))
+        .ok_or(Error::CapacityOverflow)
+}
+
+/// Builder of the CDM actions of a deployment
+pub struct CdmTxBuilder<'a> {
+    deployment: &'a Deployment,
+}
+
+impl<'a> CdmTxBuilder<'a> {
+    pub fn new(deployment: &'a Deployment) -> Self {
+        CdmTxBuilder { deployment }
+    }
+
+    /// Creates an issuer cell without classes, its type args are the type id prefix
+    pub fn create_issuer(
+        &self,
+        name: &[u8],
+        owner_lock: Script,
+        funding: &Funding,
+    ) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        let mut draft = Draft::new(funding, deployment.cell_deps(&[&deployment.issuer_type]))?;
+        let output_index = draft.outputs.len();
+        let type_ = deployment
+            .issuer_type
+            .script(&draft.type_id(output_index)[..ISSUER_TYPE_ARGS_LEN]);
+        let issuer = Issuer {
+            version: 0,
+            class_count: 0,
+            flags: 0,
+            owner_lock_hash: None,
+        };
+        let output = CellOutput::new_builder()
+            .lock(owner_lock)
+            .type_(Some(type_).pack())
+            .build();
+        draft.push_output(output, Bytes::from(issuer.to_data(name)))?;
+        draft.declare_output(output_index, CdmAction::Mint { sale_price: 0 });
+        draft.finish(funding)
+    }
+
+    /// Creates the next class of the issuer, locked like the issuer. The dynamic fields of
+    /// the class keep their size prefixes, see `script_utils::helper::to_dyn_vec`.
+    pub fn create_class(
+        &self,
+        issuer_cell: &LiveCell,
+        class: &Class,
+        funding: &Funding,
+    ) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        let issuer_type =
+            issuer_cell.type_matching(&deployment.issuer_type, "not an issuer cell")?;
+        let issuer = Issuer::from_data(&issuer_cell.data)?;
+        let class_data = class.to_data();
+        Class::from_data(&class_data)?;
+
+        let mut draft = Draft::new(
+            funding,
+            deployment.cell_deps(&[&deployment.issuer_type, &deployment.class_type]),
+        )?;
+        let input_index = draft.push_input(issuer_cell);
+        draft.declare_input(input_index, CdmAction::Update);
+
+        // Only the class count changes, the name is kept as is
+        let class_count = issuer
+            .class_count
+            .checked_add(1)
+            .ok_or(Error::CellInvalid("too many classes"))?;
+        let mut issuer_data = issuer_cell.data.to_vec();
+        issuer_data[ISSUER_CLASS_COUNT_OFFSET..(ISSUER_CLASS_COUNT_OFFSET + 4)]
+            .copy_from_slice(&class_count.to_be_bytes());
+        draft.push_output(issuer_cell.output.clone(), Bytes::from(issuer_data))?;
+
+        let issuer_type_hash = issuer_type.calc_script_hash();
+        let mut args = issuer_type_hash.as_slice()[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        args.extend_from_slice(&issuer.class_count.to_be_bytes());
+        let output = CellOutput::new_builder()
+            .lock(issuer_cell.output.lock())
+            .type_(Some(deployment.class_type.script(&args)).pack())
+            .build();
+        let output_index = draft.push_output(output, Bytes::from(class_data))?;
+        draft.declare_output(output_index, CdmAction::Mint { sale_price: 0 });
+        draft.finish(funding)
+    }
+
+    /// Mints an NFT of the class for every content and pays the class cost of each.
+    /// Classes linked to a metadata cell need it as metadata.
+    pub fn mint(
+        &self,
+        class_cell: &LiveCell,
+        metadata_cell: Option<&LiveCell>,
+        contents: &[&[u8]],
+        receiver_lock: Script,
+        funding: &Funding,
+    ) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        let class_type = class_cell.type_matching(&deployment.class_type, "not a class cell")?;
+        let class = Class::from_data(&class_cell.data)?;
+        let sale_price = class
+            .cost
+            .checked_mul(SHANNONS_PER_CKB)
+            .ok_or(Error::CapacityOverflow)?;
+
+        let mut cell_deps = deployment.cell_deps(&[&deployment.nft_type]);
+        cell_deps.push(
+            CellDep::new_builder()
+                .out_point(class_cell.out_point.clone())
+                .build(),
+        );
+        if let Some(metadata_cell) = metadata_cell {
+            cell_deps.push(
+                CellDep::new_builder()
+                    .out_point(metadata_cell.out_point.clone())
+                    .build(),
+            );
+        }
+        let mut draft = Draft::new(funding, cell_deps)?;
+
+        let class_args: Bytes = class_type.args().unpack();
+        let mut nfts_capacity = 0u64;
+        for content in contents {
+            let output_index = draft.outputs.len();
+            let mut args = class_args.to_vec();
+            args.extend_from_slice(&draft.type_id(output_index));
+            let nft = Nft {
+                version: 0,
+                data: to_dyn_vec(content),
+            };
+            let output = CellOutput::new_builder()
+                .lock(receiver_lock.clone())
+                .type_(Some(deployment.nft_type.script(&args)).pack())
+                .build();
+            draft.push_output(output, Bytes::from(nft.to_data()))?;
+            nfts_capacity = checked_sum(vec![
+                nfts_capacity,
+                draft.outputs[output_index].0.capacity().unpack(),
+            ])?;
+            draft.declare_output(output_index, CdmAction::Mint { sale_price });
+        }
+
+        // The capacity of the minted NFTs counts toward the cost
+        let total_cost = sale_price
+            .checked_mul(contents.len() as u64)
+            .ok_or(Error::CapacityOverflow)?;
+        let payment = CellOutput::new_builder()
+            .lock(deployment.payment_lock())
+            .build();
+        let payment_capacity = std::cmp::max(
+            total_cost.saturating_sub(nfts_capacity),
+            min_capacity(&payment, &Bytes::new())?,
+        );
+        draft.push_output_with_capacity(payment, Bytes::new(), payment_capacity)?;
+        draft.finish(funding)
+    }
+
+    /// Sends the NFT to receiver_lock
+    pub fn transfer(
+        &self,
+        nft_cell: &LiveCell,
+        receiver_lock: Script,
+        funding: &Funding,
+    ) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        nft_cell.type_matching(&deployment.nft_type, "not an NFT cell")?;
+        let mut draft = Draft::new(funding, deployment.cell_deps(&[&deployment.nft_type]))?;
+        let input_index = draft.push_input(nft_cell);
+        // Sending the NFT to its own lock keeps it in place
+        let action = if nft_cell.output.lock().as_slice() == receiver_lock.as_slice() {
+            CdmAction::Lock
+        } else {
+            CdmAction::Transfer
+        };
+        draft.declare_input(input_index, action);
+        let output = nft_cell
+            .output
+            .clone()
+            .as_builder()
+            .lock(receiver_lock)
+            .build();
+        draft.push_output(output, nft_cell.data.clone())?;
+        draft.finish(funding)
+    }
+
+    /// Replaces the data of the metadata cell, its flags are kept. Metadata with a history
+    /// records the previous data as the next revision.
+    pub fn update_metadata(
+        &self,
+        metadata_cell: &LiveCell,
+        data: &[u8],
+        funding: &Funding,
+    ) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        metadata_cell.type_matching(&deployment.metadata_type, "not a metadata cell")?;
+        let mut metadata = Metadata::from_data(&metadata_cell.data)?;
+        if metadata.is_frozen() {
+            return Err(Error::CellInvalid("frozen metadata cell"));
+        }
+        let prev_data_hash = blake2b_256(&metadata_cell.data);
+        metadata.data = to_dyn_u32_vec(data);
+        if let Some(history) = metadata.history.as_mut() {
+            history.revision = history
+                .revision
+                .checked_add(1)
+                .ok_or(Error::CellInvalid("too many revisions"))?;
+            history.prev_data_hash = prev_data_hash;
+        }
+
+        let mut draft = Draft::new(funding, deployment.cell_deps(&[&deployment.metadata_type]))?;
+        let input_index = draft.push_input(metadata_cell);
+        draft.declare_input(input_index, CdmAction::UpdateMetadata { prev_data_hash });
+        draft.push_output(
+            metadata_cell.output.clone(),
+            Bytes::from(metadata.to_data()),
+        )?;
+        draft.finish(funding)
+    }
+
+    /// Destroys the NFT, its capacity goes to the change cell
+    pub fn burn(&self, nft_cell: &LiveCell, funding: &Funding) -> Result<TransactionView, Error> {
+        let deployment = self.deployment;
+        nft_cell.type_matching(&deployment.nft_type, "not an NFT cell")?;
+        let mut draft = Draft::new(funding, deployment.cell_deps(&[&deployment.nft_type]))?;
+        let input_index = draft.push_input(nft_cell);
+        draft.declare_input(input_index, CdmAction::Burn);
+        draft.finish(funding)
+    }
+}
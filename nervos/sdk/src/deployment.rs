@@ -0,0 +1,66 @@
+use ckb_types::{bytes::Bytes, packed::*, prelude::*};
+use script_utils::helper::hash_types_match;
+use script_utils::registry::Registry;
+use std::iter;
+
+/// Deployed code of a CDM contract, referenced by the type scripts of its cells
+#[derive(Debug, Clone)]
+pub struct ContractRef {
+    pub code_hash: [u8; 32],
+    pub hash_type: u8,
+    /// Cell dep of the code, or of the dep group containing it
+    pub cell_dep: CellDep,
+}
+
+impl ContractRef {
+    pub fn script(&self, args: &[u8]) -> Script {
+        Script::new_builder()
+            .code_hash(self.code_hash.pack())
+            .hash_type(Byte::new(self.hash_type))
+            .args(Bytes::copy_from_slice(args).pack())
+            .build()
+    }
+
+    /// Whether the script runs this code, whichever data hash type references it
+    pub fn matches(&self, script: &Script) -> bool {
+        script.code_hash().as_slice() == &self.code_hash[..]
+            && hash_types_match(self.hash_type, script.hash_type().as_slice()[0])
+    }
+}
+
+/// Registry cell referenced as a cell dep, with its decoded data
+#[derive(Debug, Clone)]
+pub struct RegistryCell {
+    pub cell_dep: CellDep,
+    pub registry: Registry,
+}
+
+/// Contracts of a network and its registry cell, which the contracts require as a cell
+/// dep of every transaction
+#[derive(Debug, Clone)]
+pub struct Deployment {
+    pub issuer_type: ContractRef,
+    pub class_type: ContractRef,
+    pub nft_type: ContractRef,
+    pub metadata_type: ContractRef,
+    pub registry: RegistryCell,
+}
+
+impl Deployment {
+    /// Lock of the payment cell of mints
+    pub fn payment_lock(&self) -> Script {
+        let payment_lock = &self.registry.registry.payment_lock;
+        Script::new_builder()
+            .code_hash(payment_lock.code_hash.pack())
+            .hash_type(Byte::new(payment_lock.hash_type))
+            .args(Bytes::copy_from_slice(&payment_lock.args).pack())
+            .build()
+    }
+
+    /// Registry cell dep followed by the code deps of the contracts
+    pub(crate) fn cell_deps(&self, contracts: &[&ContractRef]) -> Vec<CellDep> {
+        iter::once(self.registry.cell_dep.clone())
+            .chain(contracts.iter().map(|contract| contract.cell_dep.clone()))
+            .collect()
+    }
+}
@@ -0,0 +1,39 @@
+use script_utils::error::Error as CodecError;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// Cell data rejected by the codecs of the contracts
+    Codec(CodecError),
+    /// A cell passed to the builder is not the expected kind of CDM cell
+    CellInvalid(&'static str),
+    /// The funding cells cannot pay for the outputs, the fee and the change
+    CapacityNotEnough {
+        needed: u64,
+        available: u64,
+    },
+    CapacityOverflow,
+}
+
+impl From<CodecError> for Error {
+    fn from(err: CodecError) -> Self {
+        Error::Codec(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Codec(err) => write!(f, "invalid cell data: {:?}", err),
+            Error::CellInvalid(reason) => write!(f, "invalid cell: {}", reason),
+            Error::CapacityNotEnough { needed, available } => write!(
+                f,
+                "capacity not enough: {} shannons needed, {} available",
+                needed, available
+            ),
+            Error::CapacityOverflow => write!(f, "capacity overflow"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,20 @@
+//! Builds unsigned CDM transactions off-chain with the constants and codecs of the
+//! contracts: type ids, class and NFT args, payment cells and minimum capacities.
+//!
+//! ```ignore
+//! let builder = CdmTxBuilder::new(&deployment);
+//! let tx = builder.create_issuer(b"issuer", owner_lock, &funding)?;
+//! ```
+//!
+//! Every transaction spends the funding cells first, so the type ids derive from the
+//! first funding cell, and returns the remaining capacity minus the fee to a change
+//! cell. The first witness of every lock group carries a signature placeholder.
+
+mod builder;
+mod deployment;
+mod error;
+
+pub use builder::{CdmTxBuilder, Funding, LiveCell, SIGNATURE_PLACEHOLDER_LEN};
+pub use deployment::{ContractRef, Deployment, RegistryCell};
+pub use error::Error;
+pub use script_utils;
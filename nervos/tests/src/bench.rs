@@ -0,0 +1,214 @@
+//! Cycle measurements of CDM transactions checked against a baseline file, so changes
+//! which make an action more expensive than its budget, or a batch too large for a
+//! transaction or a block, fail the tests.
+//!
+//! The baseline lists one `<name> <cycles>` per line. Benchmarks without an entry are
+//! added to it with a warning, the others must stay within their budget. Run the
+//! benchmarks with `CDM_UPDATE_CYCLES_BASELINE=1` to write every measured cycles to it,
+//! and set `CDM_CYCLES_BUDGET` to the allowed increase in percent, 5 by default.
+
+use super::fixture::MAX_CYCLES;
+use ckb_testtool::ckb_types::core::TransactionView;
+use ckb_testtool::context::Context;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum cycles of all the transactions of a block
+pub const MAX_BLOCK_CYCLES: u64 = 3_500_000_000;
+/// Allowed increase of the cycles over the baseline, in percent
+pub const DEFAULT_BUDGET: u64 = 5;
+
+const BASELINE_FILE: &str = "cycles_baseline.txt";
+const UPDATE_BASELINE_ENV_VAR: &str = "CDM_UPDATE_CYCLES_BASELINE";
+const BUDGET_ENV_VAR: &str = "CDM_CYCLES_BUDGET";
+
+pub fn baseline_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(BASELINE_FILE)
+}
+
+/// Cycles of every benchmark, by name
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Baseline(pub BTreeMap<String, u64>);
+
+impl Baseline {
+    /// Parses the baseline, lines starting with # are comments
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut cycles = BTreeMap::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next().map(str::parse::<u64>), fields.next()) {
+                (Some(name), Some(Ok(value)), None) => {
+                    cycles.insert(name.to_string(), value);
+                }
+                _ => return Err(format!("invalid baseline line: {}", line)),
+            }
+        }
+        Ok(Baseline(cycles))
+    }
+
+    /// An empty baseline when the file does not exist
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content).expect("baseline"),
+            Err(_) => Baseline::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) {
+        fs::write(path, self.to_string()).expect("write baseline");
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# Cycles of the benchmarks in tests/src/bench_tests.rs, written by")?;
+        writeln!(f, "# running them with {}=1", UPDATE_BASELINE_ENV_VAR)?;
+        for (name, cycles) in &self.0 {
+            writeln!(f, "{} {}", name, cycles)?;
+        }
+        Ok(())
+    }
+}
+
+/// A measurement failing its budget
+#[derive(Debug, Clone, PartialEq)]
+pub enum Regression {
+    /// Cycles above the baseline increased by the budget
+    OverBudget { name: String, baseline: u64, cycles: u64 },
+    /// Cycles above the limit of a transaction
+    OverTxLimit { name: String, cycles: u64 },
+    /// Cycles above the limit of a block
+    OverBlockLimit { name: String, cycles: u64 },
+}
+
+#[derive(Debug, Default)]
+pub struct CycleBench {
+    measurements: Baseline,
+}
+
+impl CycleBench {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verifies the transaction with the cycles limit of a block and records its cycles,
+    /// the limit of a transaction is checked with the regressions
+    pub fn measure(&mut self, name: &str, context: &Context, tx: &TransactionView) -> u64 {
+        let cycles = context
+            .verify_tx(tx, MAX_BLOCK_CYCLES)
+            .unwrap_or_else(|err| panic!("{} failed verification: {}", name, err));
+        self.record(name, cycles);
+        cycles
+    }
+
+    pub fn record(&mut self, name: &str, cycles: u64) {
+        self.measurements.0.insert(name.to_string(), cycles);
+    }
+
+    pub fn measurements(&self) -> &Baseline {
+        &self.measurements
+    }
+
+    /// Names of the measurements without a baseline entry
+    pub fn missing(&self, baseline: &Baseline) -> Vec<String> {
+        self.measurements
+            .0
+            .keys()
+            .filter(|name| !baseline.0.contains_key(*name))
+            .cloned()
+            .collect()
+    }
+
+    /// Measurements above the baseline increased by budget percent, or above the limit of
+    /// a transaction or of a block. Measurements missing from the baseline are not checked
+    /// against it.
+    pub fn regressions(&self, baseline: &Baseline, budget: u64) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        for (name, &cycles) in &self.measurements.0 {
+            if cycles > MAX_CYCLES {
+                regressions.push(Regression::OverTxLimit {
+                    name: name.clone(),
+                    cycles,
+                });
+            }
+            if cycles > MAX_BLOCK_CYCLES {
+                regressions.push(Regression::OverBlockLimit {
+                    name: name.clone(),
+                    cycles,
+                });
+            }
+            let base = match baseline.0.get(name) {
+                Some(&base) => base,
+                None => continue,
+            };
+            if cycles as u128 * 100 > base as u128 * (100 + budget as u128) {
+                regressions.push(Regression::OverBudget {
+                    name: name.clone(),
+                    baseline: base,
+                    cycles,
+                });
+            }
+        }
+        regressions
+    }
+
+    /// Prints the measurements next to the baseline, then either writes them to the
+    /// baseline file or asserts that they are within the budget and the limits. Limits
+    /// are enforced when the baseline is written too, and measurements missing from it
+    /// are added with a warning.
+    pub fn finish(self) {
+        let path = baseline_path();
+        let baseline = Baseline::load(&path);
+        for (name, cycles) in &self.measurements.0 {
+            match baseline.0.get(name) {
+                Some(base) => println!("{}: {} cycles, baseline {}", name, cycles, base),
+                None => println!("{}: {} cycles, no baseline", name, cycles),
+            }
+        }
+
+        if env::var(UPDATE_BASELINE_ENV_VAR).is_ok() {
+            self.measurements.save(&path);
+            let over_limits: Vec<Regression> = self
+                .regressions(&self.measurements, 0)
+                .into_iter()
+                .filter(|regression| match regression {
+                    Regression::OverTxLimit { .. } | Regression::OverBlockLimit { .. } => true,
+                    Regression::OverBudget { .. } => false,
+                })
+                .collect();
+            assert!(over_limits.is_empty(), "cycles over limits: {:?}", over_limits);
+            return;
+        }
+        let budget = match env::var(BUDGET_ENV_VAR) {
+            Ok(val) => val.parse().expect("cycles budget"),
+            Err(_) => DEFAULT_BUDGET,
+        };
+        let missing = self.missing(&baseline);
+        if !missing.is_empty() {
+            eprintln!(
+                "warning: no cycles baseline for {:?}, recording them in {}",
+                missing,
+                path.display()
+            );
+            let mut recorded = baseline.clone();
+            for name in &missing {
+                recorded.0.insert(name.clone(), self.measurements.0[name]);
+            }
+            recorded.save(&path);
+        }
+        let regressions = self.regressions(&baseline, budget);
+        assert!(
+            regressions.is_empty(),
+            "cycles over budget or limits, update {} with {}=1 if expected: {:?}",
+            path.display(),
+            UPDATE_BASELINE_ENV_VAR,
+            regressions
+        );
+    }
+}
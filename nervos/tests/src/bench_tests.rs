@@ -0,0 +1,147 @@
+use super::bench::*;
+use super::fixture::*;
+use super::TestEnv;
+
+// Native executables do not count cycles
+fn is_simulated() -> bool {
+    TestEnv::current() == TestEnv::Simulator
+}
+
+#[test]
+fn test_cycles_within_budget() {
+    if is_simulated() {
+        return;
+    }
+    let mut bench = CycleBench::new();
+
+    let (context, tx) = CdmFixture::new().create_issuer().build();
+    bench.measure("issuer_create", &context, &tx);
+    let (context, tx) = CdmFixture::new().issuer().update_issuer(1).build();
+    bench.measure("issuer_update", &context, &tx);
+
+    let (context, tx) = CdmFixture::new().issuer().create_class(1000).build();
+    bench.measure("class_create", &context, &tx);
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(2000))
+        .build();
+    bench.measure("class_update", &context, &tx);
+    let (context, tx) = CdmFixture::new().issuer().class(1000).destroy_class().build();
+    bench.measure("class_destroy", &context, &tx);
+
+    for &count in &[1u64, 10, 50, 100] {
+        let (context, tx) = CdmFixture::new()
+            .issuer()
+            .class(1000)
+            .mint(count as usize)
+            .pay(1000 * count)
+            .build();
+        bench.measure(&format!("nft_mint_{}", count), &context, &tx);
+    }
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .build();
+    bench.measure("nft_transfer", &context, &tx);
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .update_nft(b"new nft")
+        .build();
+    bench.measure("nft_update", &context, &tx);
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .burn()
+        .build();
+    bench.measure("nft_destroy", &context, &tx);
+
+    let (context, tx) = CdmFixture::new()
+        .create_metadata(MetadataData::new(b"document"))
+        .build();
+    bench.measure("metadata_create", &context, &tx);
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(MetadataData::new(b"new document"))
+        .build();
+    bench.measure("metadata_update", &context, &tx);
+
+    bench.finish();
+}
+
+#[test]
+fn test_nft_mint_100_within_tx_limit() {
+    if is_simulated() {
+        return;
+    }
+    let mut bench = CycleBench::new();
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(100)
+        .pay(100_000)
+        .build();
+    let cycles = bench.measure("nft_mint_100", &context, &tx);
+    assert!(
+        cycles <= MAX_CYCLES,
+        "minting 100 nfts takes {} cycles, over the transaction limit",
+        cycles
+    );
+}
+
+#[test]
+fn test_regressions_over_budget() {
+    if is_simulated() {
+        return;
+    }
+    let baseline = Baseline::parse("# comment\nnft_mint_1 1000\nnft_update 1000\n").expect("baseline");
+    let mut bench = CycleBench::new();
+    let (context, tx) = CdmFixture::new().issuer().class(1000).mint(1).pay(1000).build();
+    let cycles = bench.measure("nft_mint_1", &context, &tx);
+
+    assert_eq!(
+        bench.regressions(&baseline, 5),
+        vec![Regression::OverBudget {
+            name: "nft_mint_1".to_string(),
+            baseline: 1000,
+            cycles,
+        }]
+    );
+    assert!(bench.regressions(&bench.measurements().clone(), 0).is_empty());
+    assert!(Baseline::parse("nft_mint_1 many").is_err());
+}
+
+#[test]
+fn test_regressions_over_limits_and_missing_baseline() {
+    let baseline = Baseline::parse("nft_mint_1 1000\nnft_mint_1000 1000\n").expect("baseline");
+    let mut bench = CycleBench::new();
+    bench.record("nft_mint_1", 1000);
+    bench.record("nft_mint_1000", MAX_CYCLES + 1);
+    bench.record("nft_update", MAX_CYCLES + 1);
+
+    // Entries missing from the baseline are only checked against the limits
+    assert_eq!(bench.missing(&baseline), vec!["nft_update".to_string()]);
+    assert_eq!(
+        bench.regressions(&baseline, 5),
+        vec![
+            Regression::OverTxLimit {
+                name: "nft_mint_1000".to_string(),
+                cycles: MAX_CYCLES + 1,
+            },
+            Regression::OverBudget {
+                name: "nft_mint_1000".to_string(),
+                baseline: 1000,
+                cycles: MAX_CYCLES + 1,
+            },
+            Regression::OverTxLimit {
+                name: "nft_update".to_string(),
+                cycles: MAX_CYCLES + 1,
+            },
+        ]
+    );
+}
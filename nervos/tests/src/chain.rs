@@ -0,0 +1,430 @@
+//! Chain of verified transactions over a persistent live cell set, for flows where each
+//! transaction consumes the outputs of the previous ones:
+//!
+//! ```ignore
+//! let mut chain = Chain::new();
+//! let issuer = chain.create_issuer()?;
+//! let (issuer, class) = chain.create_class(&issuer, ClassData::new(1000))?;
+//! let nfts = chain.mint(&class, 2)?;
+//! chain.burn(&nfts[0])?;
+//! ```
+//!
+//! Committed outputs are linked to the tip block, so header deps and the headers of
+//! inputs can be loaded by the scripts. Blocks are produced explicitly with controllable
+//! timestamps and epochs.
+
+use super::context::TestContext;
+use super::fixture::{
+    burn_action, issuer_data, lock_action, mint_action, nft_data, transfer_action, type_id,
+    update_action, witness_args, ClassData, Deployment, LiveCell, MetadataData,
+    ISSUER_TYPE_ARGS_LEN, MAX_CYCLES, METADATA_TYPE_ARGS_LEN, SHANNONS_PER_CKB,
+};
+use ckb_testtool::ckb_error::Error as VerificationError;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{EpochNumberWithFraction, HeaderBuilder, HeaderView, TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use std::collections::HashMap;
+
+/// Average block interval of the produced blocks, in milliseconds
+pub const BLOCK_INTERVAL: u64 = 8_000;
+/// Blocks of every produced epoch
+pub const EPOCH_LENGTH: u64 = 1_800;
+
+#[derive(Debug)]
+pub enum ChainError {
+    /// The transaction spends a cell which is not live
+    DeadCell(OutPoint),
+    /// The transaction references a cell dep which is not live
+    DeadCellDep(OutPoint),
+    Verification(VerificationError),
+}
+
+impl From<VerificationError> for ChainError {
+    fn from(err: VerificationError) -> Self {
+        ChainError::Verification(err)
+    }
+}
+
+pub struct Chain {
+    context: TestContext,
+    deployment: Deployment,
+    live_cells: HashMap<OutPoint, LiveCell>,
+    headers: Vec<HeaderView>,
+    // transactions committed in the tip block
+    tip_txs_count: usize,
+}
+
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Chain {
+    /// Deploys the contracts and produces the genesis block at timestamp 0 of epoch 0
+    pub fn new() -> Self {
+        let mut context = TestContext::default();
+        let deployment = Deployment::deploy(&mut context);
+        let mut chain = Chain {
+            context,
+            deployment,
+            live_cells: HashMap::new(),
+            headers: Vec::new(),
+            tip_txs_count: 0,
+        };
+        chain.produce_block_at(0, EpochNumberWithFraction::new(0, 0, EPOCH_LENGTH));
+        // The contracts and the registry are live from the genesis block
+        let deployment = &chain.deployment;
+        let genesis_out_points = vec![
+            deployment.always_success_out_point.clone(),
+            deployment.issuer_out_point.clone(),
+            deployment.class_out_point.clone(),
+            deployment.nft_out_point.clone(),
+            deployment.metadata_out_point.clone(),
+            deployment.registry_dep.out_point(),
+        ];
+        for out_point in genesis_out_points {
+            chain.insert_live_cell(out_point);
+        }
+        chain
+    }
+
+    // Marks a cell created in the context as live
+    fn insert_live_cell(&mut self, out_point: OutPoint) -> LiveCell {
+        let (output, data) = self.context.get_cell(&out_point).expect("cell");
+        let cell = LiveCell {
+            out_point: out_point.clone(),
+            output,
+            data,
+        };
+        self.live_cells.insert(out_point, cell.clone());
+        cell
+    }
+
+    pub fn deployment(&self) -> &Deployment {
+        &self.deployment
+    }
+
+    pub fn context(&self) -> &TestContext {
+        &self.context
+    }
+
+    pub fn tip(&self) -> &HeaderView {
+        self.headers.last().expect("genesis block")
+    }
+
+    pub fn is_live(&self, out_point: &OutPoint) -> bool {
+        self.live_cells.contains_key(out_point)
+    }
+
+    /// Produces a block after the tip with the given timestamp, in milliseconds, and epoch
+    pub fn produce_block_at(&mut self, timestamp: u64, epoch: EpochNumberWithFraction) -> HeaderView {
+        let parent_hash = self
+            .headers
+            .last()
+            .map_or(Byte32::default(), |parent| parent.hash());
+        let header = HeaderBuilder::default()
+            .number((self.headers.len() as u64).pack())
+            .parent_hash(parent_hash)
+            .timestamp(timestamp.pack())
+            .epoch(epoch.full_value().pack())
+            .build();
+        self.context.insert_header(header.clone());
+        self.headers.push(header.clone());
+        self.tip_txs_count = 0;
+        header
+    }
+
+    /// Produces a block BLOCK_INTERVAL after the tip, in the next epoch after EPOCH_LENGTH
+    /// blocks
+    pub fn produce_block(&mut self) -> HeaderView {
+        let tip = self.tip();
+        let epoch = tip.epoch();
+        let next_epoch = if epoch.index() + 1 < epoch.length() {
+            EpochNumberWithFraction::new(epoch.number(), epoch.index() + 1, epoch.length())
+        } else {
+            EpochNumberWithFraction::new(epoch.number() + 1, 0, EPOCH_LENGTH)
+        };
+        let timestamp = tip.timestamp() + BLOCK_INTERVAL;
+        self.produce_block_at(timestamp, next_epoch)
+    }
+
+    /// Starts a transaction spending a new cell without type as its first input, with the
+    /// registry as cell dep
+    pub fn tx(&mut self) -> ChainTx {
+        let funding = self.fund();
+        let first_input = CellInput::new_builder()
+            .previous_output(funding.out_point)
+            .build();
+        ChainTx {
+            first_input: first_input.clone(),
+            inputs: vec![first_input],
+            outputs: Vec::new(),
+            outputs_data: Vec::new(),
+            cell_deps: vec![self.deployment.registry_dep.clone()],
+            header_deps: Vec::new(),
+            input_types: Vec::new(),
+            output_types: Vec::new(),
+        }
+    }
+
+    /// Creates a live cell without type, for transactions built outside the chain
+    pub fn fund(&mut self) -> LiveCell {
+        let out_point = self
+            .deployment
+            .funding_input(&mut self.context)
+            .previous_output();
+        self.insert_live_cell(out_point)
+    }
+
+    /// Verifies the transaction against the live cells, then replaces its inputs with its
+    /// outputs, which are returned in order. Inputs and cell deps must be live.
+    pub fn commit(&mut self, tx: TransactionView) -> Result<Vec<LiveCell>, ChainError> {
+        let tx = self.context.complete_tx(tx);
+        if let Some(input) = tx
+            .inputs()
+            .into_iter()
+            .find(|input| !self.is_live(&input.previous_output()))
+        {
+            return Err(ChainError::DeadCell(input.previous_output()));
+        }
+        if let Some(cell_dep) = tx
+            .cell_deps()
+            .into_iter()
+            .find(|cell_dep| !self.is_live(&cell_dep.out_point()))
+        {
+            return Err(ChainError::DeadCellDep(cell_dep.out_point()));
+        }
+        self.context.verify_tx(&tx, MAX_CYCLES)?;
+
+        for input in tx.inputs().into_iter() {
+            self.live_cells.remove(&input.previous_output());
+        }
+        let block_hash = self.tip().hash();
+        let tx_index = self.tip_txs_count;
+        self.tip_txs_count += 1;
+        let mut cells = Vec::new();
+        for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+            let out_point = OutPoint::new(tx.hash(), index as u32);
+            self.context
+                .create_cell_with_out_point(out_point.clone(), output.clone(), data.clone());
+            self.context
+                .link_cell_with_block(out_point.clone(), block_hash.clone(), tx_index);
+            let cell = LiveCell {
+                out_point: out_point.clone(),
+                output,
+                data,
+            };
+            self.live_cells.insert(out_point, cell.clone());
+            cells.push(cell);
+        }
+        Ok(cells)
+    }
+
+    pub fn build_script(&mut self, out_point: &OutPoint, args: &[u8]) -> Script {
+        self.context
+            .build_script(out_point, Bytes::copy_from_slice(args))
+            .expect("script")
+    }
+
+    /// Creates an issuer cell without classes
+    pub fn create_issuer(&mut self) -> Result<LiveCell, ChainError> {
+        let tx = self.tx();
+        let args = tx.type_id(0)[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.issuer_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        let output = self.deployment.cell_output(Some(type_));
+        let tx = tx.output_with_action(output, issuer_data(0), mint_action(0));
+        let cells = self.commit(tx.build())?;
+        Ok(cells[0].clone())
+    }
+
+    /// Creates the next class of the issuer, returns the updated issuer and the class
+    pub fn create_class(
+        &mut self,
+        issuer: &LiveCell,
+        class: ClassData,
+    ) -> Result<(LiveCell, LiveCell), ChainError> {
+        let mut class_count = [0u8; 4];
+        class_count.copy_from_slice(&issuer.data[1..5]);
+        let class_id = u32::from_be_bytes(class_count);
+        let mut issuer_data = issuer.data.to_vec();
+        issuer_data[1..5].copy_from_slice(&(class_id + 1).to_be_bytes());
+
+        let issuer_type = issuer.output.type_().to_opt().expect("issuer type");
+        let issuer_type_hash: [u8; 32] = issuer_type.calc_script_hash().unpack();
+        let mut args = issuer_type_hash[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        args.extend_from_slice(&class_id.to_be_bytes());
+        let out_point = self.deployment.class_out_point.clone();
+        let class_type = self.build_script(&out_point, &args);
+
+        let tx = self
+            .tx()
+            .input_with_action(issuer, update_action())
+            .output(issuer.output.clone(), Bytes::from(issuer_data))
+            .output_with_action(
+                self.deployment.cell_output(Some(class_type)),
+                class.to_bytes(),
+                mint_action(0),
+            );
+        let cells = self.commit(tx.build())?;
+        Ok((cells[0].clone(), cells[1].clone()))
+    }
+
+    /// Creates a metadata cell, its type args are the type id prefix
+    pub fn create_metadata(&mut self, metadata: MetadataData) -> Result<LiveCell, ChainError> {
+        let tx = self.tx();
+        let args = tx.type_id(0)[..METADATA_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.metadata_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        let output = self.deployment.cell_output(Some(type_));
+        let tx = tx.output_with_action(output, metadata.to_bytes(), mint_action(0));
+        let cells = self.commit(tx.build())?;
+        Ok(cells[0].clone())
+    }
+
+    /// Mints count NFTs of the class, paying its cost for each
+    pub fn mint(&mut self, class: &LiveCell, count: usize) -> Result<Vec<LiveCell>, ChainError> {
+        let mut cost = [0u8; 8];
+        cost.copy_from_slice(&class.data[1..9]);
+        let cost = u64::from_be_bytes(cost);
+        let class_type = class.output.type_().to_opt().expect("class type");
+        let class_args: Bytes = class_type.args().unpack();
+
+        let sale_price = cost * SHANNONS_PER_CKB;
+
+        let mut tx = self.tx().cell_dep(class);
+        let out_point = self.deployment.nft_out_point.clone();
+        for index in 0..count {
+            let mut args = class_args.to_vec();
+            args.extend_from_slice(&tx.type_id(index));
+            let nft_type = self.build_script(&out_point, &args);
+            let output = self.deployment.cell_output(Some(nft_type));
+            tx = tx.output_with_action(output, nft_data(b"nft"), mint_action(sale_price));
+        }
+        // The class cost is in CKB
+        let payment = self.deployment.payment_output(cost * count as u64);
+        let mut cells = self.commit(tx.output(payment, Bytes::new()).build())?;
+        cells.truncate(count);
+        Ok(cells)
+    }
+
+    /// Sends the NFT to the receiver lock
+    pub fn transfer(&mut self, nft: &LiveCell) -> Result<LiveCell, ChainError> {
+        let output = nft
+            .output
+            .clone()
+            .as_builder()
+            .lock(self.deployment.receiver_lock.clone())
+            .build();
+        let tx = self
+            .tx()
+            .input_with_action(nft, transfer_action())
+            .output(output, nft.data.clone());
+        let cells = self.commit(tx.build())?;
+        Ok(cells[0].clone())
+    }
+
+    pub fn burn(&mut self, nft: &LiveCell) -> Result<(), ChainError> {
+        let tx = self.tx().input_with_action(nft, burn_action());
+        self.commit(tx.build())?;
+        Ok(())
+    }
+
+    pub fn destroy_class(&mut self, class: &LiveCell) -> Result<(), ChainError> {
+        let tx = self.tx().input_with_action(class, burn_action());
+        self.commit(tx.build())?;
+        Ok(())
+    }
+}
+
+/// Transaction started by `Chain::tx`, every input is signed
+pub struct ChainTx {
+    first_input: CellInput,
+    inputs: Vec<CellInput>,
+    outputs: Vec<CellOutput>,
+    outputs_data: Vec<Bytes>,
+    cell_deps: Vec<CellDep>,
+    header_deps: Vec<Byte32>,
+    input_types: Vec<Option<Bytes>>,
+    output_types: Vec<Option<Bytes>>,
+}
+
+impl ChainTx {
+    /// Type ID of the output at output_index
+    pub fn type_id(&self, output_index: usize) -> [u8; 32] {
+        type_id(&self.first_input, output_index)
+    }
+
+    pub fn input(mut self, cell: &LiveCell) -> Self {
+        self.inputs.push(
+            CellInput::new_builder()
+                .previous_output(cell.out_point.clone())
+                .build(),
+        );
+        self
+    }
+
+    /// Spends the cell declaring the action in the input_type field of its witness
+    pub fn input_with_action(self, cell: &LiveCell, action: Bytes) -> Self {
+        let mut tx = self.input(cell);
+        let index = tx.inputs.len() - 1;
+        tx.input_types.resize(index + 1, None);
+        tx.input_types[index] = Some(action);
+        tx
+    }
+
+    pub fn output(mut self, output: CellOutput, data: Bytes) -> Self {
+        self.outputs.push(output);
+        self.outputs_data.push(data);
+        self
+    }
+
+    /// Creates the cell declaring the action in the output_type field of its witness
+    pub fn output_with_action(self, output: CellOutput, data: Bytes, action: Bytes) -> Self {
+        let mut tx = self.output(output, data);
+        let index = tx.outputs.len() - 1;
+        tx.output_types.resize(index + 1, None);
+        tx.output_types[index] = Some(action);
+        tx
+    }
+
+    pub fn cell_dep(mut self, cell: &LiveCell) -> Self {
+        self.cell_deps.push(
+            CellDep::new_builder()
+                .out_point(cell.out_point.clone())
+                .build(),
+        );
+        self
+    }
+
+    pub fn header_dep(mut self, header: &HeaderView) -> Self {
+        self.header_deps.push(header.hash());
+        self
+    }
+
+    pub fn build(self) -> TransactionView {
+        let (input_types, output_types) = (self.input_types, self.output_types);
+        let witnesses_len = std::cmp::max(self.inputs.len(), output_types.len());
+        let witnesses: Vec<Bytes> = (0..witnesses_len)
+            .map(|index| {
+                witness_args(
+                    index < self.inputs.len(),
+                    input_types.get(index).cloned().unwrap_or(None),
+                    output_types.get(index).cloned().unwrap_or(None),
+                )
+            })
+            .collect();
+        TransactionBuilder::default()
+            .inputs(self.inputs)
+            .outputs(self.outputs)
+            .outputs_data(self.outputs_data.pack())
+            .cell_deps(self.cell_deps)
+            .header_deps(self.header_deps)
+            .witnesses(witnesses.pack())
+            .build()
+    }
+}
@@ -0,0 +1,113 @@
+use super::chain::*;
+use super::fixture::*;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::EpochNumberWithFraction, prelude::*};
+
+#[test]
+fn test_nft_lifecycle_success() {
+    let mut chain = Chain::new();
+    let issuer = chain.create_issuer().expect("create issuer");
+    let (issuer, class) = chain
+        .create_class(&issuer, ClassData::new(1000))
+        .expect("create class");
+    let nfts = chain.mint(&class, 2).expect("mint");
+    let nft = chain.transfer(&nfts[0]).expect("transfer");
+    chain.burn(&nft).expect("burn");
+    chain.burn(&nfts[1]).expect("burn");
+    chain.destroy_class(&class).expect("destroy class");
+
+    assert!(chain.is_live(&issuer.out_point));
+    assert!(!chain.is_live(&class.out_point));
+    assert!(!chain.is_live(&nft.out_point));
+}
+
+#[test]
+fn test_create_classes_from_committed_issuer_success() {
+    let mut chain = Chain::new();
+    let issuer = chain.create_issuer().expect("create issuer");
+    let (issuer, _) = chain
+        .create_class(&issuer, ClassData::new(1000))
+        .expect("create class");
+    let (issuer, class) = chain
+        .create_class(&issuer, ClassData::new(2000))
+        .expect("create class");
+
+    assert_eq!(issuer.data, issuer_data(2));
+    let class_args: Bytes = class.output.type_().to_opt().expect("class").args().unpack();
+    assert_eq!(class_args[ISSUER_TYPE_ARGS_LEN..], 1u32.to_be_bytes()[..]);
+}
+
+#[test]
+fn test_spend_dead_cell_error() {
+    let mut chain = Chain::new();
+    let issuer = chain.create_issuer().expect("create issuer");
+    let (_, class) = chain
+        .create_class(&issuer, ClassData::new(1000))
+        .expect("create class");
+    let nfts = chain.mint(&class, 1).expect("mint");
+    chain.transfer(&nfts[0]).expect("transfer");
+
+    match chain.transfer(&nfts[0]) {
+        Err(ChainError::DeadCell(out_point)) => assert_eq!(out_point, nfts[0].out_point),
+        result => panic!("double spend committed: {:?}", result),
+    }
+    match chain.create_class(&issuer, ClassData::new(1000)) {
+        Err(ChainError::DeadCell(out_point)) => assert_eq!(out_point, issuer.out_point),
+        result => panic!("double spend committed: {:?}", result),
+    }
+}
+
+#[test]
+fn test_mint_from_destroyed_class_error() {
+    let mut chain = Chain::new();
+    let issuer = chain.create_issuer().expect("create issuer");
+    let (_, class) = chain
+        .create_class(&issuer, ClassData::new(1000))
+        .expect("create class");
+    chain.destroy_class(&class).expect("destroy class");
+
+    match chain.mint(&class, 1) {
+        Err(ChainError::DeadCellDep(out_point)) => assert_eq!(out_point, class.out_point),
+        result => panic!("mint from a destroyed class committed: {:?}", result),
+    }
+}
+
+#[test]
+fn test_produce_blocks_success() {
+    let mut chain = Chain::new();
+    let block = chain.produce_block();
+    assert_eq!(block.number(), 1);
+    assert_eq!(block.timestamp(), BLOCK_INTERVAL);
+    assert_eq!(block.epoch(), EpochNumberWithFraction::new(0, 1, EPOCH_LENGTH));
+
+    let epoch = EpochNumberWithFraction::new(3, EPOCH_LENGTH - 1, EPOCH_LENGTH);
+    chain.produce_block_at(1_000_000, epoch);
+    let block = chain.produce_block();
+    assert_eq!(block.timestamp(), 1_000_000 + BLOCK_INTERVAL);
+    assert_eq!(block.epoch(), EpochNumberWithFraction::new(4, 0, EPOCH_LENGTH));
+}
+
+#[test]
+fn test_commit_with_header_deps_success() {
+    let mut chain = Chain::new();
+    let issuer = chain.create_issuer().expect("create issuer");
+    let (_, class) = chain
+        .create_class(&issuer, ClassData::new(1000))
+        .expect("create class");
+    let minted_block = chain.tip().clone();
+    let nfts = chain.mint(&class, 1).expect("mint");
+    let header = chain.produce_block_at(
+        1_600_000_000_000,
+        EpochNumberWithFraction::new(100, 0, EPOCH_LENGTH),
+    );
+
+    let nft = &nfts[0];
+    let tx = chain
+        .tx()
+        .input_with_action(nft, lock_action())
+        .output(nft.output.clone(), nft.data.clone())
+        .header_dep(&minted_block)
+        .header_dep(&header)
+        .build();
+    let cells = chain.commit(tx).expect("commit");
+    assert_eq!(cells[0].data, nft.data);
+}
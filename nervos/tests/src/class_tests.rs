@@ -0,0 +1,407 @@
+use super::codes::{Contract, Error};
+use super::fixture::*;
+use crate::assert_errors_contain;
+use cdm_sdk::script_utils::helper::HashType;
+use ckb_testtool::ckb_types::{bytes::Bytes, prelude::*};
+
+#[test]
+fn test_create_class_cell_success() {
+    let (context, tx) = CdmFixture::new().issuer().create_class(1000).build();
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_create_two_class_cells_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .create_class(1000)
+        .create_class(2000)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_class_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(2000))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_class_cell_metadata_type_hash_success() {
+    // The link is checked by every mint, not by the class update
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = Some([1u8; 32]);
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(class)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_destroy_class_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .destroy_class()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_class_cell_without_issuer_input_error() {
+    // The issuer is created in the same transaction, so it is not an input
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .create_class(1000)
+        .set_output_data(0, issuer_data(0))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::IssuerCellsCountError, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_without_class_count_increase_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .create_class(1000)
+        .set_output_data(0, issuer_data(0))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::IssuerClassCountError, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_with_missing_class_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .create_class(1000)
+        .set_output_data(0, issuer_data(2))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::ClassCellsCountError, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_with_wrong_class_id_error() {
+    let fixture = CdmFixture::new().issuer().create_class(1000);
+    let class_args: Bytes = fixture.class_type().expect("class").args().unpack();
+    let mut args = class_args[..ISSUER_TYPE_ARGS_LEN].to_vec();
+    args.extend_from_slice(&5u32.to_be_bytes());
+    let (context, tx) = fixture.set_output_type_args(1, &args).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::ClassIdIncreaseError, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_with_unregistered_issuer_error() {
+    let (context, tx) = CdmFixture::new()
+        .unregistered_issuer()
+        .create_class(1000)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::IssuerTypeInvalid, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_with_invalid_registry_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .create_class(1000)
+        .with_registry_data(Bytes::from(vec![0u8; 10]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::RegistryDataInvalid, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_without_registry_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .create_class(1000)
+        .without_registry()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::RegistryCellMissing, 1)]
+    );
+}
+
+#[test]
+fn test_create_class_cell_with_issuer_registered_by_other_data_hash_type_success() {
+    // The issuer runs the code referenced by data hash, the registry selects another VM
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .create_class(1000)
+        .with_registry_hash_type(HashType::Data2 as u8)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_class_cell_with_issuer_registered_by_type_hash_error() {
+    // Same code hash, but a type hash references the code by the type of its cell
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .create_class(1000)
+        .with_registry_hash_type(HashType::Type as u8)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::ClassType, Error::IssuerCellsCountError, 1)]
+    );
+}
+
+#[test]
+fn test_update_class_cell_name_error() {
+    let mut class = ClassData::new(1000);
+    class.name = b"renamed".to_vec();
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(class)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ClassImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_class_cell_with_short_data_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(1000))
+        .set_output_data(0, Bytes::from(vec![0u8; 5]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ClassDataInvalid, 1)]
+    );
+}
+
+#[test]
+fn test_destroy_class_cell_without_owner_signature_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .destroy_class()
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_update_class_cell_setting_require_frozen_metadata_success() {
+    let mut class = ClassData::new(1000);
+    class.flags = CLASS_FLAG_REQUIRE_FROZEN_METADATA;
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(class)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_class_cell_clearing_require_frozen_metadata_error() {
+    let mut class = ClassData::new(1000);
+    class.flags = CLASS_FLAG_REQUIRE_FROZEN_METADATA;
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class_with(class)
+        .update_class(ClassData::new(1000))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ClassImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_destroy_class_cell_signed_by_owner_lock_hash_success() {
+    let fixture = CdmFixture::new();
+    let mut class = ClassData::new(1000);
+    class.owner_lock_hash = Some(fixture.receiver_lock_hash());
+    let (context, tx) = fixture
+        .issuer()
+        .class_with(class)
+        .destroy_class()
+        .receiver_lock_input()
+        .without_signatures()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_class_cell_without_owner_lock_hash_input_error() {
+    let fixture = CdmFixture::new();
+    let mut class = ClassData::new(1000);
+    class.owner_lock_hash = Some(fixture.receiver_lock_hash());
+    let (context, tx) = fixture
+        .issuer()
+        .class_with(class.clone())
+        .update_class(class)
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_update_class_cell_setting_owner_delegate_error() {
+    // Holders minted without the class owner acting for them
+    let mut class = ClassData::new(1000);
+    class.flags = CLASS_FLAG_OWNER_DELEGATE;
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(class)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ClassImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_class_cell_declared_as_burn_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(2000))
+        .declare_input(1, burn_action())
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ActionNotMatchTransition, 1)]
+    );
+}
+
+#[test]
+fn test_update_class_cell_declared_as_transfer_error() {
+    // The class keeps its lock
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(1000))
+        .declare_input(1, transfer_action())
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ActionNotMatchTransition, 1)]
+    );
+}
+
+#[test]
+#[cfg(not(feature = "legacy-actions"))]
+fn test_update_class_cell_without_action_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(1000))
+        .without_actions()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::ClassType, Error::ActionMissing, 1)]
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-actions")]
+fn test_update_class_cell_without_action_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(1000))
+        .without_actions()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
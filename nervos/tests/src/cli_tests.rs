@@ -0,0 +1,137 @@
+use super::chain::*;
+use super::codes::{Contract, Error as CdmError};
+use super::fixture::*;
+use super::TestEnv;
+use cdm::{compose, parse_args, verify, Command, Error, MockTx, Snapshot, MAX_CYCLES};
+use ckb_jsonrpc_types as json_types;
+use ckb_testtool::ckb_types::{bytes::Bytes, packed::*, prelude::*};
+use serde_json::{json, Value};
+
+fn is_simulated() -> bool {
+    TestEnv::current() == TestEnv::Simulator
+}
+
+fn json_cell(chain: &Chain, out_point: &OutPoint) -> Value {
+    let (output, data) = chain.context().get_cell(out_point).expect("cell");
+    json!({
+        "out_point": json_types::OutPoint::from(out_point.clone()),
+        "output": json_types::CellOutput::from(output),
+        "data": json_types::JsonBytes::from_bytes(data),
+    })
+}
+
+fn json_contract(chain: &mut Chain, out_point: &OutPoint) -> Value {
+    let script = json_types::Script::from(chain.build_script(out_point, &[]));
+    let cell_dep = CellDep::new_builder().out_point(out_point.clone()).build();
+    json!({
+        "code_hash": script.code_hash,
+        "hash_type": script.hash_type,
+        "cell_dep": json_types::CellDep::from(cell_dep),
+    })
+}
+
+/// Snapshot of the deployment with the given live cells
+fn snapshot(chain: &mut Chain, cells: &[&LiveCell]) -> Snapshot {
+    let deployment = chain.deployment().clone();
+    let mut json_cells: Vec<Value> = cells
+        .iter()
+        .map(|cell| json_cell(chain, &cell.out_point))
+        .collect();
+    // Code cells of the contracts and the registry cell
+    let deployed_out_points = [
+        deployment.issuer_out_point.clone(),
+        deployment.class_out_point.clone(),
+        deployment.nft_out_point.clone(),
+        deployment.metadata_out_point.clone(),
+        deployment.registry_dep.out_point(),
+    ];
+    for out_point in deployed_out_points.iter() {
+        json_cells.push(json_cell(chain, out_point));
+    }
+    let json = json!({
+        "deployment": {
+            "issuer_type": json_contract(chain, &deployment.issuer_out_point),
+            "class_type": json_contract(chain, &deployment.class_out_point),
+            "nft_type": json_contract(chain, &deployment.nft_out_point),
+            "metadata_type": json_contract(chain, &deployment.metadata_out_point),
+            "registry": json_types::CellDep::from(deployment.registry_dep.clone()),
+        },
+        "cells": json_cells,
+    });
+    Snapshot::from_json(&json.to_string()).expect("snapshot")
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn test_cli_create_class_success() {
+    // The CLI runs the contracts in the VM
+    if is_simulated() {
+        return;
+    }
+    let mut chain = Chain::new();
+    let issuer = chain.create_issuer().expect("create issuer");
+    let funding = chain.fund();
+    let snapshot = snapshot(&mut chain, &[&issuer, &funding]);
+
+    let issuer_arg = format!("0x{}:0", hex::encode(issuer.out_point.tx_hash().as_slice()));
+    let (command, options) = parse_args(args(&[
+        "create-class",
+        "--issuer",
+        &issuer_arg,
+        "--name",
+        "class",
+        "--cost",
+        "1000",
+        "--snapshot",
+        "snapshot.json",
+    ]))
+    .expect("parse args");
+    let tx = compose(&snapshot, &command, &options.funding, options.fee).expect("compose");
+    let mock_tx = MockTx::resolve(&snapshot, tx.clone()).expect("resolve");
+    assert_eq!(mock_tx.to_json()["mock_info"]["inputs"].as_array().expect("inputs").len(), 2);
+
+    // Issuer and class type groups
+    let cycles = verify(&mock_tx, MAX_CYCLES).expect("verify");
+    assert_eq!(cycles.len(), 2);
+    let cells = chain.commit(tx).expect("commit");
+    assert_eq!(cells[0].data, issuer_data(1));
+}
+
+#[test]
+fn test_cli_verify_invalid_issuer_error() {
+    if is_simulated() {
+        return;
+    }
+    let mut chain = Chain::new();
+    let funding = chain.fund();
+    let snapshot = snapshot(&mut chain, &[&funding]);
+    let command = Command::CreateIssuer {
+        name: b"issuer".to_vec(),
+        owner: None,
+    };
+    let tx = compose(&snapshot, &command, &[], 0).expect("compose");
+
+    // An issuer cannot be created with classes
+    let tx = tx
+        .as_advanced_builder()
+        .set_outputs_data(vec![issuer_data(1).pack(), Bytes::new().pack()])
+        .build();
+    let issuer_type = tx.outputs().get(0).expect("issuer").type_().to_opt();
+    let mock_tx = MockTx::resolve(&snapshot, tx).expect("resolve");
+    match verify(&mock_tx, MAX_CYCLES) {
+        Err(Error::Verification { script, code, .. }) => {
+            assert_eq!(Some(script), issuer_type);
+            assert_eq!(code, Some(Contract::IssuerType.exit_code(CdmError::IssuerClassCountError)));
+        }
+        result => panic!("unexpected result: {:?}", result.map(|cycles| cycles.len())),
+    }
+}
+
+#[test]
+fn test_cli_usage_error() {
+    let result = parse_args(args(&["mint", "--class", "0x00:0", "--snapshot", "snapshot.json"]));
+    assert!(matches!(result, Err(Error::Usage(_))));
+}
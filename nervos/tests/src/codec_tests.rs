@@ -0,0 +1,203 @@
+use super::fixture::{
+    burn_action, issuer_data, issuer_data_with_owner, mint_action, type_id, ClassData,
+    MetadataData,
+};
+use cdm_sdk::script_utils::action::CdmAction;
+use cdm_sdk::script_utils::attribute::{
+    parse_attributes, AttributeSchema, AttributeType, AttributeValue,
+};
+use cdm_sdk::script_utils::class::Class;
+use cdm_sdk::script_utils::error::Error;
+use cdm_sdk::script_utils::helper::{hash_types_match, HashType};
+use cdm_sdk::script_utils::issuer::Issuer;
+use cdm_sdk::script_utils::metadata::Metadata;
+use cdm_sdk::script_utils::type_id::calc_type_id;
+use ckb_testtool::ckb_types::{packed::*, prelude::*};
+
+fn record(type_: u8, key: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut record = vec![type_];
+    record.extend_from_slice(&((1 + key.len() + value.len()) as u16).to_be_bytes());
+    record.push(key.len() as u8);
+    record.extend_from_slice(key);
+    record.extend_from_slice(value);
+    record
+}
+
+#[test]
+fn test_parse_attributes_of_every_type() {
+    let mut data = record(0, b"name", b"alice");
+    data.extend(record(1, b"age", &42u64.to_be_bytes()));
+    data.extend(record(2, b"rare", &[1]));
+    data.extend(record(3, b"image", &[7u8; 32]));
+    data.extend(record(4, b"color", &[2]));
+
+    let values: Vec<AttributeValue> = parse_attributes(&data)
+        .expect("attributes")
+        .into_iter()
+        .map(|attribute| attribute.value)
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            AttributeValue::String(b"alice".to_vec()),
+            AttributeValue::U64(42),
+            AttributeValue::Bool(true),
+            AttributeValue::Hash([7u8; 32]),
+            AttributeValue::Enum(2),
+        ]
+    );
+    assert!(parse_attributes(&[]).expect("no attribute").is_empty());
+}
+
+#[test]
+fn test_parse_invalid_attributes_error() {
+    let mut repeated_key = record(2, b"rare", &[1]);
+    repeated_key.extend(record(2, b"rare", &[0]));
+    let mut truncated = record(0, b"name", b"alice");
+    truncated.pop();
+    let invalid = vec![
+        // unknown type
+        record(5, b"key", &[0]),
+        // u64 of 4 bytes
+        record(1, b"age", &42u32.to_be_bytes()),
+        // bool which is neither 0 nor 1
+        record(2, b"rare", &[2]),
+        // string which is not utf8
+        record(0, b"name", &[0xff, 0xfe]),
+        // key longer than the record
+        vec![0, 0, 2, 5, b'k'],
+        repeated_key,
+        truncated,
+    ];
+    for data in invalid {
+        assert!(parse_attributes(&data).is_err(), "{:?} is decoded", data);
+    }
+}
+
+#[test]
+fn test_attribute_schema_round_trip() {
+    let data = vec![1, 3, b'a', b'g', b'e', 0, 4, b'n', b'a', b'm', b'e'];
+    let schema = AttributeSchema::from_data(&data).expect("schema");
+    assert_eq!(
+        schema.entries,
+        vec![
+            (b"age".to_vec(), AttributeType::U64),
+            (b"name".to_vec(), AttributeType::String),
+        ]
+    );
+    assert_eq!(schema.to_data(), data);
+
+    let attributes = parse_attributes(&record(1, b"age", &42u64.to_be_bytes())).expect("age");
+    assert!(!schema.is_satisfied_by(&attributes));
+    // A repeated key is not a valid schema
+    assert!(AttributeSchema::from_data(&[1, 1, b'a', 0, 1, b'a']).is_err());
+}
+
+#[test]
+fn test_calc_type_id_matches_type_id_spec() {
+    let first_input = CellInput::new_builder()
+        .previous_output(OutPoint::new(Byte32::new([3u8; 32]), 1))
+        .since(7u64.pack())
+        .build();
+    for output_index in 0..3 {
+        assert_eq!(
+            calc_type_id(first_input.as_slice(), output_index as u64),
+            type_id(&first_input, output_index)
+        );
+    }
+    assert_ne!(type_id(&first_input, 0), type_id(&first_input, 1));
+}
+
+#[test]
+fn test_hash_types_match() {
+    let data_hash_types = [HashType::Data, HashType::Data1, HashType::Data2];
+    for hash_type in data_hash_types.iter() {
+        assert_eq!(HashType::from_u8(*hash_type as u8), Ok(*hash_type));
+        for other in data_hash_types.iter() {
+            assert!(hash_types_match(*hash_type as u8, *other as u8));
+        }
+        assert!(!hash_types_match(*hash_type as u8, HashType::Type as u8));
+        assert!(!hash_types_match(HashType::Type as u8, *hash_type as u8));
+    }
+    assert!(hash_types_match(HashType::Type as u8, HashType::Type as u8));
+    // 3 is not a hash type, it matches nothing
+    assert!(HashType::from_u8(3).is_err());
+    assert!(!hash_types_match(3, 3));
+    assert!(!hash_types_match(3, HashType::Data as u8));
+}
+
+#[test]
+fn test_cdm_action_round_trip() {
+    let actions = vec![
+        CdmAction::Mint { sale_price: 1000 },
+        CdmAction::Transfer,
+        CdmAction::Burn,
+        CdmAction::Lock,
+        CdmAction::Claim,
+        CdmAction::UpdateMetadata {
+            prev_data_hash: [5u8; 32],
+        },
+        CdmAction::Update,
+    ];
+    for action in actions {
+        assert_eq!(CdmAction::from_slice(&action.to_vec()), Ok(action.clone()));
+    }
+    assert_eq!(&burn_action()[..], &CdmAction::Burn.to_vec()[..]);
+    assert_eq!(&mint_action(7)[..], &CdmAction::Mint { sale_price: 7 }.to_vec()[..]);
+}
+
+#[test]
+fn test_parse_invalid_cdm_action_error() {
+    // A mint with the proof field it no longer has
+    let mut mint = 0u32.to_le_bytes().to_vec();
+    mint.extend_from_slice(&24u32.to_le_bytes());
+    mint.extend_from_slice(&12u32.to_le_bytes());
+    mint.extend_from_slice(&20u32.to_le_bytes());
+    mint.extend_from_slice(&7u64.to_le_bytes());
+    mint.extend_from_slice(&0u32.to_le_bytes());
+    assert!(CdmAction::from_slice(&mint).is_err());
+    // Unknown item and truncated union
+    assert!(CdmAction::from_slice(&7u32.to_le_bytes()).is_err());
+    assert!(CdmAction::from_slice(&[0u8; 3]).is_err());
+}
+
+#[test]
+fn test_owner_lock_hash_round_trip() {
+    let owner_lock_hash = Some([9u8; 32]);
+
+    let data = issuer_data_with_owner(3, owner_lock_hash);
+    let issuer = Issuer::from_data(&data).expect("issuer");
+    assert_eq!(issuer.owner_lock_hash, owner_lock_hash);
+    assert_eq!(&issuer.to_data(b"issuer")[..], &data[..]);
+    // The flagged owner lock hash is cut off
+    let mut data = data.to_vec();
+    data.pop();
+    assert_eq!(Issuer::from_data(&data).unwrap_err(), Error::IssuerDataInvalid);
+    // Trailing data of issuers created before the flags is not an owner lock hash
+    let mut data = issuer_data(3).to_vec();
+    data.extend_from_slice(&[0u8; 7]);
+    let issuer = Issuer::from_data(&data).expect("legacy issuer");
+    assert_eq!(issuer.owner_lock_hash, None);
+
+    let mut class_data = ClassData::new(1000);
+    class_data.owner_lock_hash = owner_lock_hash;
+    let data = class_data.to_bytes();
+    let class = Class::from_data(&data).expect("class");
+    assert_eq!(class.owner_lock_hash, owner_lock_hash);
+    assert_eq!(&class.to_data()[..], &data[..]);
+    assert_eq!(
+        Class::from_data(&data[..data.len() - 1]).unwrap_err(),
+        Error::ClassDataInvalid
+    );
+
+    let mut metadata_data = MetadataData::new(b"document");
+    metadata_data.owner_lock_hash = owner_lock_hash;
+    let data = metadata_data.to_bytes();
+    let metadata = Metadata::from_data(&data).expect("metadata");
+    assert_eq!(metadata.owner_lock_hash, owner_lock_hash);
+    assert_eq!(&metadata.to_data()[..], &data[..]);
+    assert_eq!(
+        Metadata::from_data(&data[..data.len() - 1]).unwrap_err(),
+        Error::MetadataDataInvalid
+    );
+}
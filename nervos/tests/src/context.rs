@@ -0,0 +1,87 @@
+//! `ckb_testtool::context::Context` selecting the verifier from the test env. With
+//! `CAPSULE_TEST_ENV=simulator` the contracts deployed with `deploy_contract` run natively,
+//! the scripts deployed with `deploy_cell` are not executed and any other script fails.
+//! The scenarios only call `verify_tx`, so they run unchanged with both verifiers.
+
+use super::simulator::Simulator;
+use super::{Loader, TestEnv};
+use ckb_testtool::ckb_error::Error as VerificationError;
+use ckb_testtool::ckb_hash::blake2b_256;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{Cycle, TransactionView},
+    packed::OutPoint,
+    prelude::*,
+};
+use ckb_testtool::context::Context;
+use std::ops::{Deref, DerefMut};
+
+pub struct TestContext {
+    context: Context,
+    loader: Loader,
+    simulator: Option<Simulator>,
+}
+
+impl Default for TestContext {
+    fn default() -> Self {
+        Self::with_test_env(TestEnv::current())
+    }
+}
+
+impl TestContext {
+    pub fn with_test_env(env: TestEnv) -> Self {
+        let simulator = match env {
+            TestEnv::Simulator => Some(Simulator::default()),
+            TestEnv::Debug | TestEnv::Release => None,
+        };
+        TestContext {
+            context: Context::default(),
+            loader: Loader::with_test_env(env),
+            simulator,
+        }
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.simulator.is_some()
+    }
+
+    /// Deploys the binary of the contract. Under the simulator the cell holds the native
+    /// executable, whose data hash selects it when a script group runs.
+    pub fn deploy_contract(&mut self, name: &str) -> OutPoint {
+        let binary = self.loader.load_binary(name);
+        if let Some(simulator) = self.simulator.as_mut() {
+            simulator.register(blake2b_256(&binary).pack(), self.loader.binary_path(name));
+        }
+        self.context.deploy_cell(binary)
+    }
+
+    /// Deploys a cell whose scripts are not CDM contracts, the simulator skips their groups
+    pub fn deploy_cell(&mut self, data: Bytes) -> OutPoint {
+        if let Some(simulator) = self.simulator.as_mut() {
+            simulator.register_external(blake2b_256(&data).pack());
+        }
+        self.context.deploy_cell(data)
+    }
+
+    /// Verifies the transaction with the VM, or natively without counting cycles
+    pub fn verify_tx(&self, tx: &TransactionView, max_cycles: u64) -> Result<Cycle, VerificationError> {
+        match &self.simulator {
+            Some(simulator) => simulator.verify_tx(&self.context, tx).map(|_| 0),
+            None => self.context.verify_tx(tx, max_cycles),
+        }
+    }
+}
+
+impl Deref for TestContext {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        &self.context
+    }
+}
+
+impl DerefMut for TestContext {
+    fn deref_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+}
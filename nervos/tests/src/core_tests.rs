@@ -0,0 +1,181 @@
+//! Codecs of the portable core of script-utils, built without ckb-std as off-chain
+//! crates use it
+
+use cdm_sdk::script_utils::class::{Class, CLASS_FLAG_REQUIRE_FROZEN_METADATA};
+use cdm_sdk::script_utils::error::Error;
+use cdm_sdk::script_utils::issuer::Issuer;
+use cdm_sdk::script_utils::metadata::{
+    Metadata, METADATA_FLAG_CHUNKED, METADATA_FLAG_HISTORY, METADATA_FLAG_SCHEMA,
+};
+use cdm_sdk::script_utils::nft::Nft;
+use cdm_sdk::script_utils::registry::Registry;
+
+fn dyn_field(content: &[u8]) -> Vec<u8> {
+    let mut field = (content.len() as u16).to_be_bytes().to_vec();
+    field.extend_from_slice(content);
+    field
+}
+
+fn class_data() -> Vec<u8> {
+    let mut data = vec![0u8];
+    data.extend_from_slice(&1000u64.to_be_bytes());
+    data.extend(dyn_field(b"class"));
+    data.extend(dyn_field(b"description"));
+    data.extend(dyn_field(&[3u8; 32]));
+    data.extend(dyn_field(b"renderer"));
+    data.push(CLASS_FLAG_REQUIRE_FROZEN_METADATA);
+    data
+}
+
+fn metadata_data() -> Vec<u8> {
+    let mut data = dyn_field(b"metadata");
+    data.extend_from_slice(&8u32.to_be_bytes());
+    data.extend_from_slice(b"document");
+    data.push(METADATA_FLAG_HISTORY | METADATA_FLAG_CHUNKED | METADATA_FLAG_SCHEMA);
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.extend_from_slice(&[4u8; 32]);
+    data.extend_from_slice(&0u16.to_be_bytes());
+    data.extend_from_slice(&2u16.to_be_bytes());
+    data.extend_from_slice(&[5u8; 20]);
+    // u64 attribute "age"
+    data.extend(dyn_field(&[1, 3, b'a', b'g', b'e']));
+    data
+}
+
+fn registry_data(hash_type: u8) -> Vec<u8> {
+    let mut data = vec![0u8];
+    for code_hash in &[[1u8; 32], [2u8; 32], [3u8; 32]] {
+        data.extend_from_slice(code_hash);
+        data.push(hash_type);
+    }
+    data.extend(dyn_field(&[9u8; 20]));
+    data
+}
+
+#[test]
+fn test_issuer_round_trip() {
+    let mut data = vec![0u8];
+    data.extend_from_slice(&7u32.to_be_bytes());
+    data.extend(dyn_field(b"issuer"));
+    let issuer = Issuer::from_data(&data).expect("issuer");
+    assert_eq!(issuer.class_count, 7);
+    assert_eq!(issuer.owner_lock_hash, None);
+    assert_eq!(issuer.to_data(b"issuer"), data);
+}
+
+#[test]
+fn test_parse_invalid_issuer_error() {
+    let mut data = vec![1u8];
+    data.extend_from_slice(&7u32.to_be_bytes());
+    data.extend(dyn_field(b"issuer"));
+    assert_eq!(Issuer::from_data(&data).unwrap_err(), Error::VersionInvalid);
+    assert_eq!(Issuer::from_data(&data[..4]).unwrap_err(), Error::IssuerDataInvalid);
+}
+
+#[test]
+fn test_class_round_trip() {
+    let data = class_data();
+    let class = Class::from_data(&data).expect("class");
+    assert_eq!(class.cost, 1000);
+    assert!(class.requires_frozen_metadata());
+    assert!(!class.delegates_to_owner());
+    assert_eq!(class.metadata_type_hash(), Some(&[3u8; 32][..]));
+    assert_eq!(class.to_data(), data);
+}
+
+#[test]
+fn test_parse_invalid_class_error() {
+    let data = class_data();
+    // The description size is past the end of the data
+    assert_eq!(Class::from_data(&data[..20]).unwrap_err(), Error::ClassDataInvalid);
+    assert_eq!(Class::from_data(&data[..5]).unwrap_err(), Error::ClassDataInvalid);
+}
+
+#[test]
+fn test_nft_round_trip() {
+    let mut data = vec![0u8];
+    data.extend(dyn_field(b"nft"));
+    let nft = Nft::from_data(&data).expect("nft");
+    assert_eq!(nft.data_content(), b"nft");
+    assert_eq!(nft.to_data(), data);
+
+    assert_eq!(Nft::from_data(&data[..4]).unwrap_err(), Error::NFTDataInvalid);
+    data[0] = 1;
+    assert_eq!(Nft::from_data(&data).unwrap_err(), Error::VersionInvalid);
+}
+
+#[test]
+fn test_metadata_round_trip() {
+    let data = metadata_data();
+    let metadata = Metadata::from_data(&data).expect("metadata");
+    assert_eq!(metadata.data_content(), b"document");
+    let history = metadata.history.as_ref().expect("history");
+    assert_eq!((history.revision, history.prev_data_hash), (2, [4u8; 32]));
+    let chunk = metadata.chunk.as_ref().expect("chunk");
+    assert_eq!((chunk.index, chunk.count, chunk.document_id), (0, 2, [5u8; 20]));
+    assert_eq!(metadata.schema.as_ref().expect("schema").entries.len(), 1);
+    assert_eq!(metadata.to_data(), data);
+}
+
+#[test]
+fn test_metadata_immutable_equal() {
+    let metadata = Metadata::from_data(&metadata_data()).expect("metadata");
+    let mut other = metadata.clone();
+    other.history = None;
+    assert!(metadata.immutable_equal(&other));
+    // The chunk count is as immutable as the index and the document id
+    let mut other = metadata.clone();
+    other.chunk.as_mut().expect("chunk").count = 3;
+    assert!(!metadata.immutable_equal(&other));
+}
+
+#[test]
+fn test_parse_invalid_metadata_error() {
+    let data = metadata_data();
+    // The schema is flagged but cut off
+    assert_eq!(
+        Metadata::from_data(&data[..data.len() - 3]).unwrap_err(),
+        Error::MetadataDataInvalid
+    );
+    // The chunk index must be below the chunk count
+    let chunk_index = data.len() - 7 - 24;
+    let mut data = data;
+    data[chunk_index + 1] = 2;
+    assert_eq!(Metadata::from_data(&data).unwrap_err(), Error::MetadataChunkInvalid);
+}
+
+#[test]
+fn test_parse_registry() {
+    let registry = Registry::from_data(&registry_data(0)).expect("registry");
+    assert_eq!(registry.class_type.code_hash, [1u8; 32]);
+    assert_eq!(registry.issuer_type.code_hash, [2u8; 32]);
+    assert_eq!(registry.payment_lock.code_hash, [3u8; 32]);
+    assert_eq!(registry.payment_lock.args, vec![9u8; 20]);
+    assert!(registry.class_type.args.is_empty());
+}
+
+#[test]
+fn test_parse_invalid_registry_error() {
+    // 3 is not a hash type
+    assert_eq!(Registry::from_data(&registry_data(3)).unwrap_err(), Error::RegistryDataInvalid);
+    let data = registry_data(0);
+    assert_eq!(Registry::from_data(&data[..50]).unwrap_err(), Error::RegistryDataInvalid);
+    // The payment lock args are cut off
+    assert_eq!(
+        Registry::from_data(&data[..data.len() - 1]).unwrap_err(),
+        Error::RegistryDataInvalid
+    );
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let class = Class::from_data(&class_data()).expect("class");
+    let json = serde_json::to_string(&class).expect("serialize");
+    let decoded: Class = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(decoded.to_data(), class_data());
+
+    let metadata = Metadata::from_data(&metadata_data()).expect("metadata");
+    let json = serde_json::to_string(&metadata).expect("serialize");
+    let decoded: Metadata = serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(decoded.to_data(), metadata_data());
+}
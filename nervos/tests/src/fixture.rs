@@ -0,0 +1,1187 @@
+//! Fluent builder of CDM transactions. The fixture deploys the four contracts and a
+//! registry cell referencing them, computes the args and type ids of every cell and
+//! returns a transaction ready to be verified:
+//!
+//! ```ignore
+//! let (context, tx) = CdmFixture::new().issuer().class(1000).mint(2).pay(2000).build();
+//! context.verify_tx(&tx, MAX_CYCLES).expect("pass verification");
+//! ```
+//!
+//! Cells set up with `issuer`, `class` and `metadata` exist before the transaction, the
+//! `create_*` methods, `mint` and `pay` add outputs to the transaction. Every CDM cell
+//! transition declares the action matching it unless another one is declared with
+//! `declare_input` or `declare_output`.
+
+use super::codes::{Contract, Error};
+use super::network::REGISTRY_TYPE_ID;
+use super::context::TestContext;
+use ckb_testtool::ckb_error::Error as VerificationError;
+use ckb_testtool::ckb_hash::{blake2b_256, new_blake2b};
+use ckb_testtool::ckb_script::ScriptError;
+use ckb_testtool::ckb_types::{
+    bytes::Bytes,
+    core::{TransactionBuilder, TransactionView},
+    packed::*,
+    prelude::*,
+};
+use ckb_testtool::builtin::ALWAYS_SUCCESS;
+
+pub use cdm::MAX_CYCLES;
+pub use cdm_sdk::script_utils::class::{
+    CLASS_FLAG_OWNER_DELEGATE, CLASS_FLAG_OWNER_LOCK_HASH, CLASS_FLAG_REQUIRE_FROZEN_METADATA,
+};
+pub use cdm_sdk::script_utils::issuer::{ISSUER_FLAG_OWNER_LOCK_HASH, ISSUER_TYPE_ARGS_LEN};
+pub use cdm_sdk::script_utils::metadata::{
+    METADATA_FLAG_ATTRIBUTES, METADATA_FLAG_CHUNKED, METADATA_FLAG_FROZEN, METADATA_FLAG_HISTORY,
+    METADATA_FLAG_OWNER_LOCK_HASH, METADATA_FLAG_SCHEMA, METADATA_TYPE_ARGS_LEN,
+};
+use cdm_sdk::script_utils::type_id::TYPE_ID_CODE_HASH;
+
+pub const SHANNONS_PER_CKB: u64 = 100_000_000;
+
+const CELL_CAPACITY: u64 = 1_000 * SHANNONS_PER_CKB;
+const WITNESS_LOCK_LEN: usize = 65;
+const SELLER_LOCK_ARGS: [u8; 20] = [1u8; 20];
+const RECEIVER_LOCK_ARGS: [u8; 20] = [2u8; 20];
+const TYPE: u8 = 1;
+
+fn dyn_field(content: &[u8]) -> Vec<u8> {
+    let mut field = (content.len() as u16).to_be_bytes().to_vec();
+    field.extend_from_slice(content);
+    field
+}
+
+fn dyn_u32_field(content: &[u8]) -> Vec<u8> {
+    let mut field = (content.len() as u32).to_be_bytes().to_vec();
+    field.extend_from_slice(content);
+    field
+}
+
+fn script_ref(script: &Script) -> Vec<u8> {
+    let mut script_ref = script.code_hash().as_slice().to_vec();
+    script_ref.extend_from_slice(script.hash_type().as_slice());
+    script_ref
+}
+
+fn registry_type() -> Script {
+    Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.pack())
+        .args(Bytes::copy_from_slice(&REGISTRY_TYPE_ID).pack())
+        .hash_type(Byte::new(TYPE))
+        .build()
+}
+
+/// Registry cell data referencing the deployed class and issuer types
+fn registry_data(class_type: &Script, issuer_type: &Script, payment_lock: &Script) -> Bytes {
+    let payment_lock_args: Bytes = payment_lock.args().unpack();
+    let mut data = vec![0u8];
+    data.extend(script_ref(class_type));
+    data.extend(script_ref(issuer_type));
+    data.extend(script_ref(payment_lock));
+    data.extend(dyn_field(&payment_lock_args));
+    Bytes::from(data)
+}
+
+/// Creates the registry cell of the network, which every CDM transaction references
+pub fn deploy_registry(
+    context: &mut TestContext,
+    class_type: &Script,
+    issuer_type: &Script,
+    payment_lock: &Script,
+    lock: &Script,
+) -> CellDep {
+    let registry_out_point = context.create_cell(
+        CellOutput::new_builder()
+            .capacity(CELL_CAPACITY.pack())
+            .lock(lock.clone())
+            .type_(Some(registry_type()).pack())
+            .build(),
+        registry_data(class_type, issuer_type, payment_lock),
+    );
+    CellDep::new_builder().out_point(registry_out_point).build()
+}
+
+pub fn issuer_data(class_count: u32) -> Bytes {
+    issuer_data_with_owner(class_count, None)
+}
+
+/// Issuer data ending with the flags and the owner lock hash, if any
+pub fn issuer_data_with_owner(class_count: u32, owner_lock_hash: Option<[u8; 32]>) -> Bytes {
+    let mut data = vec![0u8];
+    data.extend_from_slice(&class_count.to_be_bytes());
+    data.extend(dyn_field(b"issuer"));
+    if let Some(owner_lock_hash) = owner_lock_hash {
+        data.push(ISSUER_FLAG_OWNER_LOCK_HASH);
+        data.extend_from_slice(&owner_lock_hash);
+    }
+    Bytes::from(data)
+}
+
+pub fn nft_data(content: &[u8]) -> Bytes {
+    let mut data = vec![0u8];
+    data.extend(dyn_field(content));
+    Bytes::from(data)
+}
+
+fn molecule_table(fields: &[&[u8]]) -> Vec<u8> {
+    let header_len = 4 * (fields.len() + 1);
+    let total_len = header_len + fields.iter().map(|field| field.len()).sum::<usize>();
+    let mut table = (total_len as u32).to_le_bytes().to_vec();
+    let mut offset = header_len;
+    for field in fields {
+        table.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += field.len();
+    }
+    for field in fields {
+        table.extend_from_slice(field);
+    }
+    table
+}
+
+fn cdm_action(item_id: u32, fields: &[&[u8]]) -> Bytes {
+    let mut action = item_id.to_le_bytes().to_vec();
+    action.extend(molecule_table(fields));
+    Bytes::from(action)
+}
+
+/// CdmAction::Mint, sale_price is in shannons
+pub fn mint_action(sale_price: u64) -> Bytes {
+    cdm_action(0, &[&sale_price.to_le_bytes()])
+}
+
+pub fn transfer_action() -> Bytes {
+    cdm_action(1, &[])
+}
+
+pub fn burn_action() -> Bytes {
+    cdm_action(2, &[])
+}
+
+pub fn lock_action() -> Bytes {
+    cdm_action(3, &[])
+}
+
+pub fn claim_action() -> Bytes {
+    cdm_action(4, &[])
+}
+
+pub fn update_metadata_action(prev_data_hash: &[u8; 32]) -> Bytes {
+    cdm_action(5, &[prev_data_hash])
+}
+
+pub fn update_action() -> Bytes {
+    cdm_action(6, &[])
+}
+
+/// WitnessArgs with a signature placeholder when signed and the declared actions
+pub fn witness_args(signed: bool, input_type: Option<Bytes>, output_type: Option<Bytes>) -> Bytes {
+    let lock = if signed {
+        Some(Bytes::from(vec![0u8; WITNESS_LOCK_LEN]))
+    } else {
+        None
+    };
+    WitnessArgs::new_builder()
+        .lock(lock.pack())
+        .input_type(input_type.pack())
+        .output_type(output_type.pack())
+        .build()
+        .as_bytes()
+}
+
+/// Error returned by the contract as the type script of the input at index
+pub fn input_type_error(contract: Contract, error: Error, index: usize) -> VerificationError {
+    ScriptError::ValidationFailure(contract.exit_code(error))
+        .input_type_script(index)
+        .into()
+}
+
+/// Error returned by the contract as the type script of the output at index, for scripts
+/// without group inputs
+pub fn output_type_error(contract: Contract, error: Error, index: usize) -> VerificationError {
+    ScriptError::ValidationFailure(contract.exit_code(error))
+        .output_type_script(index)
+        .into()
+}
+
+/// Fields of a class cell, encoded as described in `script-utils/src/class.rs`
+#[derive(Debug, Clone)]
+pub struct ClassData {
+    pub cost: u64,
+    pub name: Vec<u8>,
+    pub description: Vec<u8>,
+    pub metadata_type_hash: Option<[u8; 32]>,
+    pub renderer: Vec<u8>,
+    pub flags: u8,
+    pub owner_lock_hash: Option<[u8; 32]>,
+}
+
+impl ClassData {
+    pub fn new(cost: u64) -> Self {
+        ClassData {
+            cost,
+            name: b"class".to_vec(),
+            description: b"class of the fixture".to_vec(),
+            metadata_type_hash: None,
+            renderer: Vec::new(),
+            flags: 0,
+            owner_lock_hash: None,
+        }
+    }
+
+    pub fn flags(&self) -> u8 {
+        match self.owner_lock_hash {
+            Some(_) => self.flags | CLASS_FLAG_OWNER_LOCK_HASH,
+            None => self.flags,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut data = vec![0u8];
+        data.extend_from_slice(&self.cost.to_be_bytes());
+        data.extend(dyn_field(&self.name));
+        data.extend(dyn_field(&self.description));
+        let metadata_type_hash = self.metadata_type_hash.map_or(Vec::new(), |hash| hash.to_vec());
+        data.extend(dyn_field(&metadata_type_hash));
+        // Flags can only follow a renderer
+        let flags = self.flags();
+        if !self.renderer.is_empty() || flags != 0 {
+            data.extend(dyn_field(&self.renderer));
+        }
+        if flags != 0 {
+            data.push(flags);
+        }
+        if let Some(owner_lock_hash) = &self.owner_lock_hash {
+            data.extend_from_slice(owner_lock_hash);
+        }
+        Bytes::from(data)
+    }
+}
+
+/// Fields of a metadata cell, encoded as described in `script-utils/src/metadata.rs`.
+/// The history, chunked, schema and owner lock hash flags are set from the optional fields.
+#[derive(Debug, Clone)]
+pub struct MetadataData {
+    pub name: Vec<u8>,
+    pub data: Vec<u8>,
+    pub flags: u8,
+    /// revision and prev_data_hash
+    pub history: Option<(u32, [u8; 32])>,
+    /// index, count and document_id
+    pub chunk: Option<(u16, u16, [u8; METADATA_TYPE_ARGS_LEN])>,
+    pub schema: Option<Vec<u8>>,
+    pub owner_lock_hash: Option<[u8; 32]>,
+}
+
+impl MetadataData {
+    pub fn new(data: &[u8]) -> Self {
+        MetadataData {
+            name: b"metadata".to_vec(),
+            data: data.to_vec(),
+            flags: 0,
+            history: None,
+            chunk: None,
+            schema: None,
+            owner_lock_hash: None,
+        }
+    }
+
+    pub fn flags(&self) -> u8 {
+        let mut flags = self.flags;
+        if self.history.is_some() {
+            flags |= METADATA_FLAG_HISTORY;
+        }
+        if self.chunk.is_some() {
+            flags |= METADATA_FLAG_CHUNKED;
+        }
+        if self.schema.is_some() {
+            flags |= METADATA_FLAG_SCHEMA;
+        }
+        if self.owner_lock_hash.is_some() {
+            flags |= METADATA_FLAG_OWNER_LOCK_HASH;
+        }
+        flags
+    }
+
+    pub fn to_bytes(&self) -> Bytes {
+        let mut data = dyn_field(&self.name);
+        data.extend(dyn_u32_field(&self.data));
+        let flags = self.flags();
+        if flags != 0 {
+            data.push(flags);
+        }
+        if let Some((revision, prev_data_hash)) = &self.history {
+            data.extend_from_slice(&revision.to_be_bytes());
+            data.extend_from_slice(prev_data_hash);
+        }
+        if let Some((index, count, document_id)) = &self.chunk {
+            data.extend_from_slice(&index.to_be_bytes());
+            data.extend_from_slice(&count.to_be_bytes());
+            data.extend_from_slice(document_id);
+        }
+        if let Some(schema) = &self.schema {
+            data.extend(dyn_field(schema));
+        }
+        if let Some(owner_lock_hash) = &self.owner_lock_hash {
+            data.extend_from_slice(owner_lock_hash);
+        }
+        Bytes::from(data)
+    }
+}
+
+/// Type ID of the output at output_index of a transaction whose first input is
+/// first_input
+pub fn type_id(first_input: &CellInput, output_index: usize) -> [u8; 32] {
+    let mut blake2b = new_blake2b();
+    blake2b.update(first_input.as_slice());
+    blake2b.update(&(output_index as u64).to_le_bytes());
+    let mut ret = [0; 32];
+    blake2b.finalize(&mut ret);
+    ret
+}
+
+/// Contracts, locks and registry cell deployed in a context
+#[derive(Clone)]
+pub struct Deployment {
+    pub lock_script: Script,
+    pub receiver_lock: Script,
+    pub payment_lock: Script,
+    pub always_success_out_point: OutPoint,
+    pub issuer_out_point: OutPoint,
+    pub class_out_point: OutPoint,
+    pub nft_out_point: OutPoint,
+    pub metadata_out_point: OutPoint,
+    pub registry_dep: CellDep,
+}
+
+impl Deployment {
+    pub fn deploy(context: &mut TestContext) -> Self {
+        let issuer_out_point = context.deploy_contract("issuer-type");
+        let class_out_point = context.deploy_contract("class-type");
+        let nft_out_point = context.deploy_contract("nft-type");
+        let metadata_out_point = context.deploy_contract("metadata");
+        let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
+
+        let lock_script = context
+            .build_script(&always_success_out_point, Bytes::new())
+            .expect("script");
+        let receiver_lock = context
+            .build_script(&always_success_out_point, Bytes::copy_from_slice(&RECEIVER_LOCK_ARGS))
+            .expect("script");
+        let payment_lock = context
+            .build_script(&always_success_out_point, Bytes::copy_from_slice(&SELLER_LOCK_ARGS))
+            .expect("script");
+
+        // The contracts resolve the class type, issuer type and payment lock from it
+        let class_type = context
+            .build_script(&class_out_point, Bytes::new())
+            .expect("script");
+        let issuer_type = context
+            .build_script(&issuer_out_point, Bytes::new())
+            .expect("script");
+        let registry_dep =
+            deploy_registry(context, &class_type, &issuer_type, &payment_lock, &lock_script);
+
+        Deployment {
+            lock_script,
+            receiver_lock,
+            payment_lock,
+            always_success_out_point,
+            issuer_out_point,
+            class_out_point,
+            nft_out_point,
+            metadata_out_point,
+            registry_dep,
+        }
+    }
+
+    /// Cell locked by the default lock
+    pub fn cell_output(&self, type_: Option<Script>) -> CellOutput {
+        CellOutput::new_builder()
+            .capacity(CELL_CAPACITY.pack())
+            .lock(self.lock_script.clone())
+            .type_(type_.pack())
+            .build()
+    }
+
+    /// Cell of amount CKB locked by the payment lock of the registry
+    pub fn payment_output(&self, amount: u64) -> CellOutput {
+        CellOutput::new_builder()
+            .capacity((amount * SHANNONS_PER_CKB).pack())
+            .lock(self.payment_lock.clone())
+            .build()
+    }
+
+    /// Creates a cell without type to be consumed as the first input of a transaction
+    pub fn funding_input(&self, context: &mut TestContext) -> CellInput {
+        let funding_out_point = context.create_cell(self.cell_output(None), Bytes::new());
+        CellInput::new_builder()
+            .previous_output(funding_out_point)
+            .build()
+    }
+}
+
+/// Cell created in the context before the transaction
+#[derive(Debug, Clone)]
+pub struct LiveCell {
+    pub out_point: OutPoint,
+    pub output: CellOutput,
+    pub data: Bytes,
+}
+
+struct IssuerState {
+    type_: Script,
+    class_count: u32,
+    owner_lock_hash: Option<[u8; 32]>,
+    // Data after the name of issuers created before the flags, replacing them
+    legacy_tail: Option<Vec<u8>>,
+    // Set once the issuer cell is an output of the transaction
+    output_index: Option<usize>,
+}
+
+impl IssuerState {
+    fn data(&self, class_count: u32) -> Bytes {
+        match &self.legacy_tail {
+            Some(tail) => {
+                let mut data = issuer_data(class_count).to_vec();
+                data.extend_from_slice(tail);
+                Bytes::from(data)
+            }
+            None => issuer_data_with_owner(class_count, self.owner_lock_hash),
+        }
+    }
+}
+
+struct ClassState {
+    type_: Script,
+    // None for classes created in the transaction
+    cell: Option<LiveCell>,
+    dep_added: bool,
+}
+
+struct MetadataState {
+    type_: Script,
+    // None for metadata cells created in the transaction
+    cell: Option<LiveCell>,
+}
+
+#[derive(Default, Clone)]
+struct WitnessState {
+    input_type: Option<Bytes>,
+    output_type: Option<Bytes>,
+}
+
+pub struct CdmFixture {
+    context: TestContext,
+    deployment: Deployment,
+    first_input: CellInput,
+    inputs: Vec<CellInput>,
+    outputs: Vec<CellOutput>,
+    outputs_data: Vec<Bytes>,
+    cell_deps: Vec<CellDep>,
+    witnesses: Vec<WitnessState>,
+    signed: bool,
+    declared: bool,
+    live_cells_count: u32,
+    issuer: Option<IssuerState>,
+    classes: Vec<ClassState>,
+    nfts: Vec<LiveCell>,
+    metadata: Vec<MetadataState>,
+}
+
+impl Default for CdmFixture {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CdmFixture {
+    /// Deploys the contracts and the funding cell consumed as the first input, from which
+    /// the type ids of the created cells are derived
+    pub fn new() -> Self {
+        let mut context = TestContext::default();
+        let deployment = Deployment::deploy(&mut context);
+        let first_input = deployment.funding_input(&mut context);
+        CdmFixture {
+            context,
+            cell_deps: vec![deployment.registry_dep.clone()],
+            deployment,
+            first_input: first_input.clone(),
+            inputs: vec![first_input],
+            outputs: Vec::new(),
+            outputs_data: Vec::new(),
+            witnesses: Vec::new(),
+            signed: true,
+            declared: true,
+            live_cells_count: 0,
+            issuer: None,
+            classes: Vec::new(),
+            nfts: Vec::new(),
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Type ID of the output at output_index
+    pub fn type_id(&self, output_index: usize) -> [u8; 32] {
+        type_id(&self.first_input, output_index)
+    }
+
+    // Unique id for the args of cells created before the transaction
+    fn live_id(&mut self) -> [u8; 32] {
+        self.live_cells_count += 1;
+        blake2b_256(self.live_cells_count.to_le_bytes())
+    }
+
+    fn build_script(&mut self, out_point: &OutPoint, args: &[u8]) -> Script {
+        self.context
+            .build_script(out_point, Bytes::copy_from_slice(args))
+            .expect("script")
+    }
+
+    fn cell_output(&self, type_: Option<Script>) -> CellOutput {
+        self.deployment.cell_output(type_)
+    }
+
+    fn create_live_cell(&mut self, type_: Script, data: Bytes) -> LiveCell {
+        let output = self.cell_output(Some(type_));
+        let out_point = self.context.create_cell(output.clone(), data.clone());
+        LiveCell {
+            out_point,
+            output,
+            data,
+        }
+    }
+
+    fn push_input(&mut self, cell: &LiveCell) -> usize {
+        self.inputs.push(
+            CellInput::new_builder()
+                .previous_output(cell.out_point.clone())
+                .build(),
+        );
+        self.inputs.len() - 1
+    }
+
+    fn push_output(&mut self, type_: Script, data: Bytes) -> usize {
+        self.outputs.push(self.cell_output(Some(type_)));
+        self.outputs_data.push(data);
+        self.outputs.len() - 1
+    }
+
+    // Output with the type, lock and data of a consumed cell
+    fn push_cell_output(&mut self, output: CellOutput, data: Bytes) -> usize {
+        self.outputs.push(output);
+        self.outputs_data.push(data);
+        self.outputs.len() - 1
+    }
+
+    fn push_cell_dep(&mut self, cell: &LiveCell) {
+        self.cell_deps.push(
+            CellDep::new_builder()
+                .out_point(cell.out_point.clone())
+                .build(),
+        );
+    }
+
+    // A consumed cell cannot be referenced as a cell dep
+    fn remove_cell_dep(&mut self, out_point: &OutPoint) {
+        self.cell_deps
+            .retain(|cell_dep| cell_dep.out_point().as_slice() != out_point.as_slice());
+    }
+
+    fn witness_mut(&mut self, index: usize) -> &mut WitnessState {
+        if self.witnesses.len() <= index {
+            self.witnesses.resize(index + 1, WitnessState::default());
+        }
+        &mut self.witnesses[index]
+    }
+
+    fn issuer_mut(&mut self) -> &mut IssuerState {
+        self.issuer
+            .as_mut()
+            .expect("call issuer() or create_issuer() first")
+    }
+
+    fn next_class_id(&mut self) -> u32 {
+        let issuer = self.issuer_mut();
+        issuer.class_count += 1;
+        issuer.class_count - 1
+    }
+
+    fn build_class_type(&mut self, class_id: u32) -> Script {
+        let issuer_type_hash: [u8; 32] = self.issuer_mut().type_.calc_script_hash().unpack();
+        let mut args = issuer_type_hash[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        args.extend_from_slice(&class_id.to_be_bytes());
+        let out_point = self.deployment.class_out_point.clone();
+        self.build_script(&out_point, &args)
+    }
+
+    fn consume_issuer(&mut self) -> LiveCell {
+        let issuer = self.issuer_mut();
+        let (type_, data) = (issuer.type_.clone(), issuer.data(issuer.class_count));
+        let cell = self.create_live_cell(type_, data);
+        self.push_input(&cell);
+        cell
+    }
+
+    // Consumes the issuer cell the first time and returns the index of its output
+    fn issuer_output(&mut self) -> usize {
+        if let Some(output_index) = self.issuer_mut().output_index {
+            return output_index;
+        }
+        let cell = self.consume_issuer();
+        let output_index = self.push_cell_output(cell.output, cell.data);
+        self.issuer_mut().output_index = Some(output_index);
+        output_index
+    }
+
+    /// An issuer cell created before the transaction. It is created in the context when
+    /// the transaction consumes it, so its class count includes the classes added before.
+    pub fn issuer(mut self) -> Self {
+        let args = self.live_id()[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.issuer_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        self.issuer = Some(IssuerState {
+            type_,
+            class_count: 0,
+            owner_lock_hash: None,
+            legacy_tail: None,
+            output_index: None,
+        });
+        self
+    }
+
+    /// An issuer cell created before the transaction whose type script does not run the
+    /// issuer type of the registry
+    pub fn unregistered_issuer(mut self) -> Self {
+        let args = self.live_id()[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.always_success_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        self.issuer = Some(IssuerState {
+            type_,
+            class_count: 0,
+            owner_lock_hash: None,
+            legacy_tail: None,
+            output_index: None,
+        });
+        self
+    }
+
+    /// Creates an issuer cell in the transaction
+    pub fn create_issuer(mut self) -> Self {
+        let output_index = self.outputs.len();
+        let args = self.type_id(output_index)[..ISSUER_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.issuer_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        self.push_output(type_.clone(), issuer_data(0));
+        self.issuer = Some(IssuerState {
+            type_,
+            class_count: 0,
+            owner_lock_hash: None,
+            legacy_tail: None,
+            output_index: Some(output_index),
+        });
+        self
+    }
+
+    /// Sets the owner lock hash of the issuer cell created before the transaction
+    pub fn issuer_owner(mut self, owner_lock_hash: [u8; 32]) -> Self {
+        self.issuer_mut().owner_lock_hash = Some(owner_lock_hash);
+        self
+    }
+
+    /// Ends the data of the issuer cell created before the transaction with the tail of an
+    /// issuer created before the flags
+    pub fn legacy_issuer_tail(mut self, tail: &[u8]) -> Self {
+        self.issuer_mut().legacy_tail = Some(tail.to_vec());
+        self
+    }
+
+    /// Consumes the issuer cell and sets the class count of its output
+    pub fn update_issuer(mut self, class_count: u32) -> Self {
+        let output_index = self.issuer_output();
+        self.outputs_data[output_index] = self.issuer_mut().data(class_count);
+        self
+    }
+
+    /// Consumes the issuer cell without an output
+    pub fn destroy_issuer(mut self) -> Self {
+        self.consume_issuer();
+        self
+    }
+
+    /// A class cell of the issuer created before the transaction
+    pub fn class(self, cost: u64) -> Self {
+        self.class_with(ClassData::new(cost))
+    }
+
+    pub fn class_with(mut self, class: ClassData) -> Self {
+        let class_id = self.next_class_id();
+        let type_ = self.build_class_type(class_id);
+        let cell = self.create_live_cell(type_.clone(), class.to_bytes());
+        self.classes.push(ClassState {
+            type_,
+            cell: Some(cell),
+            dep_added: false,
+        });
+        self
+    }
+
+    /// Creates a class cell in the transaction, the issuer cell is consumed and its class
+    /// count increased
+    pub fn create_class(self, cost: u64) -> Self {
+        self.create_class_with(ClassData::new(cost))
+    }
+
+    pub fn create_class_with(mut self, class: ClassData) -> Self {
+        let issuer_index = self.issuer_output();
+        let class_id = self.next_class_id();
+        let class_count = self.issuer_mut().class_count;
+        self.outputs_data[issuer_index] = self.issuer_mut().data(class_count);
+        let type_ = self.build_class_type(class_id);
+        self.push_output(type_.clone(), class.to_bytes());
+        self.classes.push(ClassState {
+            type_,
+            cell: None,
+            dep_added: false,
+        });
+        self
+    }
+
+    /// Mints count NFTs of the last class, which is added as a cell dep
+    pub fn mint(self, count: usize) -> Self {
+        self.mint_with(count, b"nft")
+    }
+
+    pub fn mint_with(mut self, count: usize, content: &[u8]) -> Self {
+        let class = self.classes.last_mut().expect("call class() first");
+        let class_cell = class
+            .cell
+            .clone()
+            .expect("NFTs are minted from a class created before the transaction");
+        let class_args: Bytes = class.type_.args().unpack();
+        if !class.dep_added {
+            class.dep_added = true;
+            self.push_cell_dep(&class_cell);
+        }
+        let out_point = self.deployment.nft_out_point.clone();
+        for _ in 0..count {
+            let mut args = class_args.to_vec();
+            args.extend_from_slice(&self.type_id(self.outputs.len()));
+            let type_ = self.build_script(&out_point, &args);
+            self.push_output(type_, nft_data(content));
+        }
+        self
+    }
+
+    fn consume_class(&mut self) -> LiveCell {
+        let class = self.classes.last_mut().expect("call class() first");
+        let cell = class
+            .cell
+            .take()
+            .expect("only a class created before the transaction can be consumed");
+        class.dep_added = false;
+        self.remove_cell_dep(&cell.out_point);
+        self.push_input(&cell);
+        cell
+    }
+
+    /// Consumes the last class cell and replaces its data
+    pub fn update_class(mut self, class: ClassData) -> Self {
+        let cell = self.consume_class();
+        self.push_cell_output(cell.output, class.to_bytes());
+        self
+    }
+
+    /// Consumes the last class cell without an output
+    pub fn destroy_class(mut self) -> Self {
+        self.consume_class();
+        self
+    }
+
+    /// An NFT of the last class created before the transaction
+    pub fn nft(mut self, content: &[u8]) -> Self {
+        let class = self.classes.last().expect("call class() first");
+        let class_args: Bytes = class.type_.args().unpack();
+        let mut args = class_args.to_vec();
+        args.extend_from_slice(&self.live_id());
+        let out_point = self.deployment.nft_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        let cell = self.create_live_cell(type_, nft_data(content));
+        self.nfts.push(cell);
+        self
+    }
+
+    fn consume_nft(&mut self) -> LiveCell {
+        let cell = self.nfts.pop().expect("call nft() first");
+        self.push_input(&cell);
+        cell
+    }
+
+    /// Consumes the last NFT and sends it to another lock
+    pub fn transfer(mut self) -> Self {
+        let cell = self.consume_nft();
+        let output = cell
+            .output
+            .as_builder()
+            .lock(self.deployment.receiver_lock.clone())
+            .build();
+        self.push_cell_output(output, cell.data);
+        self
+    }
+
+    /// Consumes the last NFT and replaces its content
+    pub fn update_nft(mut self, content: &[u8]) -> Self {
+        let cell = self.consume_nft();
+        self.push_cell_output(cell.output, nft_data(content));
+        self
+    }
+
+    /// Consumes the last NFT without an output
+    pub fn burn(mut self) -> Self {
+        self.consume_nft();
+        self
+    }
+
+    /// Pays amount CKB to the payment lock of the registry
+    pub fn pay(mut self, amount: u64) -> Self {
+        let output = self.deployment.payment_output(amount);
+        self.push_cell_output(output, Bytes::new());
+        self
+    }
+
+    /// A metadata cell created before the transaction, referenced as a cell dep
+    pub fn metadata(mut self, metadata: MetadataData) -> Self {
+        let args = self.live_id()[..METADATA_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.metadata_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        let cell = self.create_live_cell(type_.clone(), metadata.to_bytes());
+        self.push_cell_dep(&cell);
+        self.metadata.push(MetadataState {
+            type_,
+            cell: Some(cell),
+        });
+        self
+    }
+
+    /// Creates a metadata cell in the transaction
+    pub fn create_metadata(mut self, metadata: MetadataData) -> Self {
+        let output_index = self.outputs.len();
+        let args = self.type_id(output_index)[..METADATA_TYPE_ARGS_LEN].to_vec();
+        let out_point = self.deployment.metadata_out_point.clone();
+        let type_ = self.build_script(&out_point, &args);
+        self.push_output(type_.clone(), metadata.to_bytes());
+        self.metadata.push(MetadataState { type_, cell: None });
+        self
+    }
+
+    fn consume_metadata(&mut self) -> LiveCell {
+        let metadata = self.metadata.last_mut().expect("call metadata() first");
+        let cell = metadata
+            .cell
+            .take()
+            .expect("only a metadata cell created before the transaction can be consumed");
+        self.remove_cell_dep(&cell.out_point);
+        self.push_input(&cell);
+        cell
+    }
+
+    /// Consumes the last metadata cell and replaces its data
+    pub fn update_metadata(mut self, metadata: MetadataData) -> Self {
+        let cell = self.consume_metadata();
+        self.push_cell_output(cell.output, metadata.to_bytes());
+        self
+    }
+
+    /// Consumes the last metadata cell without an output
+    pub fn destroy_metadata(mut self) -> Self {
+        self.consume_metadata();
+        self
+    }
+
+    /// Type hash of the last metadata cell, to link a class to it
+    pub fn metadata_type_hash(&self) -> Option<[u8; 32]> {
+        self.metadata
+            .last()
+            .map(|metadata| metadata.type_.calc_script_hash().unpack())
+    }
+
+    /// Type args of the last metadata cell, the document id of a chunked document
+    pub fn metadata_args(&self) -> Option<[u8; METADATA_TYPE_ARGS_LEN]> {
+        self.metadata.last().map(|metadata| {
+            let args: Bytes = metadata.type_.args().unpack();
+            let mut document_id = [0u8; METADATA_TYPE_ARGS_LEN];
+            document_id.copy_from_slice(&args);
+            document_id
+        })
+    }
+
+    /// Declares an action in the input_type witness field of the input at index
+    pub fn declare_input(mut self, index: usize, action: Bytes) -> Self {
+        self.witness_mut(index).input_type = Some(action);
+        self
+    }
+
+    /// Declares an action in the output_type witness field of the output at index
+    pub fn declare_output(mut self, index: usize, action: Bytes) -> Self {
+        self.witness_mut(index).output_type = Some(action);
+        self
+    }
+
+    /// Lock hash of the cells consumed by `receiver_lock_input`
+    pub fn receiver_lock_hash(&self) -> [u8; 32] {
+        self.deployment.receiver_lock.calc_script_hash().unpack()
+    }
+
+    /// Consumes a cell of the receiver lock, which unlocks without a witness lock like
+    /// locks keeping their signature elsewhere than in WitnessArgs.lock
+    pub fn receiver_lock_input(mut self) -> Self {
+        let output = self
+            .cell_output(None)
+            .as_builder()
+            .lock(self.deployment.receiver_lock.clone())
+            .build();
+        let out_point = self.context.create_cell(output.clone(), Bytes::new());
+        let cell = LiveCell {
+            out_point,
+            output,
+            data: Bytes::new(),
+        };
+        self.push_input(&cell);
+        self
+    }
+
+    /// Adds the last class created before the transaction as a cell dep
+    pub fn class_dep(mut self) -> Self {
+        let class = self.classes.last_mut().expect("call class() first");
+        let class_cell = class.cell.clone().expect("class created before the transaction");
+        if !class.dep_added {
+            class.dep_added = true;
+            self.push_cell_dep(&class_cell);
+        }
+        self
+    }
+
+    /// Declares no action but those declared explicitly
+    pub fn without_actions(mut self) -> Self {
+        self.declared = false;
+        self
+    }
+
+    /// Leaves the witness locks empty, so no owner signs the transaction
+    pub fn without_signatures(mut self) -> Self {
+        self.signed = false;
+        self
+    }
+
+    /// Replaces the data of the output at index, to build invalid transitions
+    pub fn set_output_data(mut self, index: usize, data: Bytes) -> Self {
+        self.outputs_data[index] = data;
+        self
+    }
+
+    /// Replaces the type args of the output at index
+    pub fn set_output_type_args(mut self, index: usize, args: &[u8]) -> Self {
+        let type_ = self.outputs[index]
+            .type_()
+            .to_opt()
+            .expect("output has a type script");
+        let type_ = type_.as_builder().args(Bytes::copy_from_slice(args).pack()).build();
+        self.outputs[index] = self.outputs[index]
+            .clone()
+            .as_builder()
+            .type_(Some(type_).pack())
+            .build();
+        self
+    }
+
+    /// Appends a copy of the output at index, so its type script has two group outputs
+    pub fn duplicate_output(mut self, index: usize) -> Self {
+        let (output, data) = (self.outputs[index].clone(), self.outputs_data[index].clone());
+        self.push_cell_output(output, data);
+        self
+    }
+
+    /// Replaces the registry cell dep with a cell holding data
+    pub fn with_registry_data(mut self, data: Bytes) -> Self {
+        let registry_output = self.cell_output(Some(registry_type()));
+        let registry_out_point = self.context.create_cell(registry_output, data);
+        self.cell_deps[0] = CellDep::new_builder().out_point(registry_out_point).build();
+        self
+    }
+
+    /// Replaces the registry cell dep with one referencing the class and issuer types by
+    /// hash_type, with the code hash of their deployed code
+    pub fn with_registry_hash_type(mut self, hash_type: u8) -> Self {
+        let (class_out_point, issuer_out_point) = (
+            self.deployment.class_out_point.clone(),
+            self.deployment.issuer_out_point.clone(),
+        );
+        let class_type = self.build_script(&class_out_point, &[]);
+        let issuer_type = self.build_script(&issuer_out_point, &[]);
+        let with_hash_type =
+            |script: Script| script.as_builder().hash_type(Byte::new(hash_type)).build();
+        let data = registry_data(
+            &with_hash_type(class_type),
+            &with_hash_type(issuer_type),
+            &self.deployment.payment_lock,
+        );
+        self.with_registry_data(data)
+    }
+
+    /// Removes the registry cell dep
+    pub fn without_registry(mut self) -> Self {
+        let registry_out_point = self.deployment.registry_dep.out_point();
+        self.remove_cell_dep(&registry_out_point);
+        self
+    }
+
+    /// Removes the cell dep of the last class, NFTs are minted without their class
+    pub fn without_class_dep(mut self) -> Self {
+        let class_cell = self
+            .classes
+            .last()
+            .and_then(|class| class.cell.clone())
+            .expect("call class() first");
+        self.remove_cell_dep(&class_cell.out_point);
+        self
+    }
+
+    pub fn issuer_type(&self) -> Option<Script> {
+        self.issuer.as_ref().map(|issuer| issuer.type_.clone())
+    }
+
+    pub fn class_type(&self) -> Option<Script> {
+        self.classes.last().map(|class| class.type_.clone())
+    }
+
+    // Sale price of an NFT of the class referenced by a cell dep, 0 without class dep
+    fn sale_price(&self, nft_type: &Script) -> u64 {
+        let class_code_hash = self.contract_code_hash(&self.deployment.class_out_point);
+        let nft_args: Bytes = nft_type.args().unpack();
+        self.cell_deps
+            .iter()
+            .filter_map(|cell_dep| self.context.get_cell(&cell_dep.out_point()))
+            .find(|(output, _)| {
+                output.type_().to_opt().map_or(false, |type_| {
+                    let args: Bytes = type_.args().unpack();
+                    type_.code_hash() == class_code_hash && nft_args.starts_with(&args)
+                })
+            })
+            .and_then(|(_, data)| {
+                let mut cost = [0u8; 8];
+                cost.copy_from_slice(data.get(1..9)?);
+                u64::from_be_bytes(cost).checked_mul(SHANNONS_PER_CKB)
+            })
+            .unwrap_or(0)
+    }
+
+    fn contract_code_hash(&self, out_point: &OutPoint) -> Byte32 {
+        self.context
+            .build_script(out_point, Bytes::new())
+            .expect("script")
+            .code_hash()
+    }
+
+    // Declares the action of every CDM script group without declaration from its cell
+    // transition, in the first group input or the first group output
+    fn declare_transitions(&mut self) {
+        let deployment = &self.deployment;
+        let [issuer, class, nft, metadata] = [
+            &deployment.issuer_out_point,
+            &deployment.class_out_point,
+            &deployment.nft_out_point,
+            &deployment.metadata_out_point,
+        ]
+        .map(|out_point| self.contract_code_hash(out_point));
+        let inputs: Vec<(CellOutput, Bytes)> = self
+            .inputs
+            .iter()
+            .map(|input| self.context.get_cell(&input.previous_output()).expect("input"))
+            .collect();
+        let mut types: Vec<Script> = Vec::new();
+        let cell_types = inputs
+            .iter()
+            .map(|(output, _)| output)
+            .chain(self.outputs.iter())
+            .filter_map(|output| output.type_().to_opt());
+        for type_ in cell_types {
+            let code_hash = type_.code_hash();
+            let is_cdm = [&issuer, &class, &nft, &metadata].contains(&&code_hash);
+            if is_cdm && types.iter().all(|other| other.as_slice() != type_.as_slice()) {
+                types.push(type_);
+            }
+        }
+
+        let has_type = |output: &CellOutput, type_: &Script| {
+            output.type_().to_opt().map_or(false, |other| other.as_slice() == type_.as_slice())
+        };
+        for type_ in types {
+            let group_input = inputs.iter().position(|(output, _)| has_type(output, &type_));
+            let group_output = self.outputs.iter().position(|output| has_type(output, &type_));
+            match (group_input, group_output) {
+                (Some(input), output) => {
+                    if self.witness_mut(input).input_type.is_some() {
+                        continue;
+                    }
+                    let (input_cell, input_data) = &inputs[input];
+                    let lock_changed = |output: &CellOutput| {
+                        output.lock().as_slice() != input_cell.lock().as_slice()
+                    };
+                    let action = match output.map(|output| &self.outputs[output]) {
+                        None => burn_action(),
+                        Some(output) if lock_changed(output) => transfer_action(),
+                        Some(_) if type_.code_hash() == metadata => {
+                            update_metadata_action(&blake2b_256(input_data))
+                        }
+                        Some(_) if type_.code_hash() == nft => lock_action(),
+                        Some(_) => update_action(),
+                    };
+                    self.witness_mut(input).input_type = Some(action);
+                }
+                (None, Some(output)) => {
+                    if self.witness_mut(output).output_type.is_some() {
+                        continue;
+                    }
+                    let sale_price = if type_.code_hash() == nft {
+                        self.sale_price(&type_)
+                    } else {
+                        0
+                    };
+                    self.witness_mut(output).output_type = Some(mint_action(sale_price));
+                }
+                (None, None) => {}
+            }
+        }
+    }
+
+    /// Completes the transaction with the cell deps of the contracts and a witness with a
+    /// signature placeholder for every input, unless it is built without signatures
+    pub fn build(mut self) -> (TestContext, TransactionView) {
+        if self.declared {
+            self.declare_transitions();
+        }
+        let inputs_len = self.inputs.len();
+        self.witness_mut(inputs_len - 1);
+        let signed = self.signed;
+        let witnesses: Vec<Bytes> = self
+            .witnesses
+            .iter()
+            .enumerate()
+            .map(|(index, witness)| {
+                witness_args(
+                    signed && index < inputs_len,
+                    witness.input_type.clone(),
+                    witness.output_type.clone(),
+                )
+            })
+            .collect();
+        let tx = TransactionBuilder::default()
+            .inputs(self.inputs)
+            .outputs(self.outputs)
+            .outputs_data(self.outputs_data.pack())
+            .cell_deps(self.cell_deps)
+            .witnesses(witnesses.pack())
+            .build();
+        let tx = self.context.complete_tx(tx);
+        (self.context, tx)
+    }
+}
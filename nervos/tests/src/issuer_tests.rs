@@ -0,0 +1,296 @@
+use super::codes::{Contract, Error};
+use super::fixture::*;
+use crate::assert_errors_contain;
+use ckb_testtool::ckb_types::bytes::Bytes;
+
+#[test]
+fn test_create_issuer_cell_success() {
+    let (context, tx) = CdmFixture::new().create_issuer().build();
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_issuer_cell_success() {
+    let (context, tx) = CdmFixture::new().issuer().update_issuer(1).build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_destroy_issuer_cell_success() {
+    let (context, tx) = CdmFixture::new().issuer().destroy_issuer().build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_issuer_cell_with_invalid_type_id_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .set_output_type_args(0, &[0u8; ISSUER_TYPE_ARGS_LEN])
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::TypeArgsInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_two_issuer_cells_with_same_type_error() {
+    let (context, tx) = CdmFixture::new().create_issuer().duplicate_output(0).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::IssuerCellsCountError, 0)]
+    );
+}
+
+#[test]
+fn test_create_issuer_cell_with_short_data_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .set_output_data(0, Bytes::from(vec![0u8; 3]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::IssuerDataInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_issuer_cell_with_unknown_version_error() {
+    let mut data = issuer_data(0).to_vec();
+    data[0] = 1;
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .set_output_data(0, Bytes::from(data))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::VersionInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_issuer_cell_with_classes_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .set_output_data(0, issuer_data(1))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::IssuerClassCountError, 0)]
+    );
+}
+
+#[test]
+fn test_update_issuer_cell_with_decreased_class_count_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_issuer(0)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::IssuerType, Error::IssuerClassCountError, 1)]
+    );
+}
+
+#[test]
+fn test_update_issuer_cell_without_owner_signature_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .update_issuer(1)
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::IssuerType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_update_issuer_cell_signed_by_owner_lock_hash_success() {
+    // The owner lock keeps its signature outside WitnessArgs.lock
+    let fixture = CdmFixture::new();
+    let owner_lock_hash = fixture.receiver_lock_hash();
+    let (context, tx) = fixture
+        .issuer()
+        .issuer_owner(owner_lock_hash)
+        .update_issuer(1)
+        .receiver_lock_input()
+        .without_signatures()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_destroy_issuer_cell_without_owner_lock_hash_input_error() {
+    let fixture = CdmFixture::new();
+    let owner_lock_hash = fixture.receiver_lock_hash();
+    let (context, tx) = fixture
+        .issuer()
+        .issuer_owner(owner_lock_hash)
+        .destroy_issuer()
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::IssuerType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+
+#[test]
+fn test_update_legacy_issuer_cell_success() {
+    // Data after the name written before the flags, its first byte reads as flags without
+    // the owner lock hash flag
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .legacy_issuer_tail(&[0u8, 0xff, 0xff, 0xff])
+        .create_class(1000)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_issuer_cell_with_junk_witness_lock_error() {
+    // The signature placeholder is junk no lock verified, it does not stand in for the
+    // owner lock hash
+    let fixture = CdmFixture::new();
+    let owner_lock_hash = fixture.receiver_lock_hash();
+    let (context, tx) = fixture
+        .issuer()
+        .issuer_owner(owner_lock_hash)
+        .update_issuer(1)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::IssuerType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_destroy_issuer_cell_with_classes_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .destroy_issuer()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::IssuerType, Error::IssuerCellCannotDestroyed, 1)]
+    );
+}
+
+#[test]
+fn test_create_issuer_cell_with_invalid_action_witness_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .declare_output(0, Bytes::from(vec![0u8; 3]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::ActionWitnessInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_issuer_cell_declared_as_burn_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .declare_output(0, burn_action())
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::ActionNotMatchTransition, 0)]
+    );
+}
+
+#[test]
+#[cfg(not(feature = "legacy-actions"))]
+fn test_create_issuer_cell_without_action_error() {
+    let (context, tx) = CdmFixture::new().create_issuer().without_actions().build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::ActionMissing, 0)]
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-actions")]
+fn test_create_issuer_cell_without_action_success() {
+    let (context, tx) = CdmFixture::new().create_issuer().without_actions().build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_issuer_and_metadata_cells_in_batch_success() {
+    let (context, tx) = CdmFixture::new()
+        .create_issuer()
+        .create_issuer()
+        .create_metadata(MetadataData::new(b"first document"))
+        .create_issuer()
+        .create_metadata(MetadataData::new(b"second document"))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_issuer_cells_in_batch_with_invalid_type_id_error() {
+    let fixture = CdmFixture::new().create_issuer().create_issuer().create_issuer();
+    // Every issuer of the batch is checked against the type id of its own output
+    let args = fixture.type_id(3)[..ISSUER_TYPE_ARGS_LEN].to_vec();
+    let (context, tx) = fixture.set_output_type_args(2, &args).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::IssuerType, Error::TypeArgsInvalid, 2)]
+    );
+}
@@ -4,14 +4,72 @@ use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+#[cfg(test)]
+mod bench_tests;
+#[cfg(test)]
+mod chain_tests;
+#[cfg(test)]
+mod cli_tests;
+#[cfg(test)]
+mod class_tests;
+#[cfg(test)]
+mod codec_tests;
+#[cfg(test)]
+mod core_tests;
+#[cfg(test)]
+mod issuer_tests;
+#[cfg(test)]
+mod metadata_tests;
+#[cfg(test)]
+mod mutation_tests;
 #[cfg(test)]
 mod nft_tests;
+#[cfg(test)]
+mod sdk_tests;
+
+/// Error codes returned by the contracts
+#[path = "../../contracts/script-utils/src/codes.rs"]
+pub mod codes;
+
+/// Deployment constants shared with the contracts, selected by the same features
+pub use cdm_sdk::script_utils::network;
+
+/// Context verifying transactions with the VM or the native simulator
+pub mod context;
+
+/// Native execution of the contracts with ckb-x64-simulator
+pub mod simulator;
+
+/// Builder of CDM transactions for the scenarios
+pub mod fixture;
+
+/// Chain of committed transactions for multi-transaction flows
+pub mod chain;
+
+/// Mutations of valid transactions which the contracts must reject
+pub mod mutation;
+
+/// Cycle measurements checked against a baseline
+pub mod bench;
 
 const TEST_ENV_VAR: &str = "CAPSULE_TEST_ENV";
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TestEnv {
     Debug,
     Release,
+    /// Native contract executables run with ckb-x64-simulator, see `simulator.rs`
+    Simulator,
+}
+
+impl TestEnv {
+    /// Selected by the CAPSULE_TEST_ENV variable, debug by default
+    pub fn current() -> Self {
+        match env::var(TEST_ENV_VAR) {
+            Ok(val) => val.parse().expect("test env"),
+            Err(_) => TestEnv::Debug,
+        }
+    }
 }
 
 impl FromStr for TestEnv {
@@ -21,6 +79,7 @@ impl FromStr for TestEnv {
         match s.to_lowercase().as_str() {
             "debug" => Ok(TestEnv::Debug),
             "release" => Ok(TestEnv::Release),
+            "simulator" => Ok(TestEnv::Simulator),
             _ => Err("no match"),
         }
     }
@@ -30,46 +89,52 @@ pub struct Loader(PathBuf);
 
 impl Default for Loader {
     fn default() -> Self {
-        let test_env = match env::var(TEST_ENV_VAR) {
-            Ok(val) => val.parse().expect("test env"),
-            Err(_) => TestEnv::Debug,
-        };
-        Self::with_test_env(test_env)
+        Self::with_test_env(TestEnv::current())
     }
 }
 
 impl Loader {
     fn with_test_env(env: TestEnv) -> Self {
-        let load_prefix = match env {
-            TestEnv::Debug => "debug",
-            TestEnv::Release => "release",
-        };
         let dir = env::current_dir().unwrap();
         let mut base_path = PathBuf::new();
         base_path.push(dir);
         base_path.push("..");
-        base_path.push("build");
-        base_path.push(load_prefix);
+        match env {
+            TestEnv::Debug => base_path.push("build/debug"),
+            TestEnv::Release => base_path.push("build/release"),
+            // Built with the simulator feature of the contracts into target/simulator
+            TestEnv::Simulator => base_path.push("target/simulator/debug"),
+        }
         Loader(base_path)
     }
 
+    pub fn binary_path(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+
     pub fn load_binary(&self, name: &str) -> Bytes {
-        let mut path = self.0.clone();
-        path.push(name);
-        fs::read(path).expect("binary").into()
+        fs::read(self.binary_path(name)).expect("binary").into()
     }
 }
 
+/// Asserts that a verification error is one of the expected errors, which are built
+/// with `ScriptError::ValidationFailure(code).input_type_script(index)` and the like
 #[macro_export]
 macro_rules! assert_errors_contain {
-    ($err:expr, $errors:expr) => {
-        type Error = ckb_tool::ckb_error::Error;
+    ($err:expr, $errors:expr) => {{
+        type Error = ckb_testtool::ckb_error::Error;
         let err_ = Into::<Error>::into($err).to_string();
-        let result = $errors
+        let errors: Vec<String> = $errors
             .into_iter()
-            .any(|error| err_ == Into::<Error>::into(Error::from(error)).to_string());
-        assert!(result);
-    };
+            .map(|error| Into::<Error>::into(error).to_string())
+            .collect();
+        assert!(
+            errors.contains(&err_),
+            "{} is not one of {:?}",
+            err_,
+            errors
+        );
+    }};
     ($err:expr, $errors:expr,) => {
         $crate::assert_errors_contain!($err, $errors);
     };
@@ -0,0 +1,665 @@
+use super::codes::{Contract, Error};
+use super::fixture::*;
+use crate::assert_errors_contain;
+use ckb_testtool::ckb_hash::blake2b_256;
+use ckb_testtool::ckb_types::bytes::Bytes;
+
+fn with_history(metadata: &MetadataData, revision: u32, prev_data_hash: [u8; 32]) -> MetadataData {
+    let mut metadata = metadata.clone();
+    metadata.history = Some((revision, prev_data_hash));
+    metadata
+}
+
+fn chunk(data: &[u8], index: u16, count: u16, document_id: [u8; METADATA_TYPE_ARGS_LEN]) -> MetadataData {
+    let mut metadata = MetadataData::new(data);
+    metadata.chunk = Some((index, count, document_id));
+    metadata
+}
+
+fn frozen(data: &[u8]) -> MetadataData {
+    let mut metadata = MetadataData::new(data);
+    metadata.flags = METADATA_FLAG_FROZEN;
+    metadata
+}
+
+fn head_chunk_id(fixture: &CdmFixture, output_index: usize) -> [u8; METADATA_TYPE_ARGS_LEN] {
+    let mut document_id = [0u8; METADATA_TYPE_ARGS_LEN];
+    document_id.copy_from_slice(&fixture.type_id(output_index)[..METADATA_TYPE_ARGS_LEN]);
+    document_id
+}
+
+#[test]
+fn test_create_metadata_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .create_metadata(MetadataData::new(b"document"))
+        .build();
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+#[test]
+fn test_update_metadata_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(MetadataData::new(b"new document"))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_with_history_success() {
+    let input = MetadataData::new(b"document");
+    let output = with_history(
+        &MetadataData::new(b"new document"),
+        1,
+        blake2b_256(input.to_bytes()),
+    );
+    let (context, tx) = CdmFixture::new()
+        .metadata(input.clone())
+        .update_metadata(output)
+        .declare_input(1, update_metadata_action(&blake2b_256(input.to_bytes())))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_destroy_metadata_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .destroy_metadata()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_signed_by_owner_lock_hash_success() {
+    let fixture = CdmFixture::new();
+    let mut input = MetadataData::new(b"document");
+    input.owner_lock_hash = Some(fixture.receiver_lock_hash());
+    let mut output = MetadataData::new(b"new document");
+    output.owner_lock_hash = input.owner_lock_hash;
+    let (context, tx) = fixture
+        .metadata(input)
+        .update_metadata(output)
+        .receiver_lock_input()
+        .without_signatures()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_destroy_metadata_cell_without_owner_lock_hash_input_error() {
+    let fixture = CdmFixture::new();
+    let mut metadata = MetadataData::new(b"document");
+    metadata.owner_lock_hash = Some(fixture.receiver_lock_hash());
+    let (context, tx) = fixture
+        .metadata(metadata)
+        .destroy_metadata()
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+
+#[test]
+fn test_update_metadata_cell_with_junk_witness_lock_error() {
+    let fixture = CdmFixture::new();
+    let mut input = MetadataData::new(b"document");
+    input.owner_lock_hash = Some(fixture.receiver_lock_hash());
+    let mut output = MetadataData::new(b"new document");
+    output.owner_lock_hash = input.owner_lock_hash;
+    let (context, tx) = fixture.metadata(input).update_metadata(output).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_create_chunked_metadata_cells_success() {
+    let fixture = CdmFixture::new();
+    let document_id = head_chunk_id(&fixture, 0);
+    let (context, tx) = fixture
+        .create_metadata(chunk(b"first", 0, 2, document_id))
+        .create_metadata(chunk(b"second", 1, 2, document_id))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_two_metadata_cells_with_same_type_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_metadata(MetadataData::new(b"document"))
+        .duplicate_output(0)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::MetadataCellsCountError, 0)]
+    );
+}
+
+#[test]
+fn test_create_metadata_cell_with_short_data_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_metadata(MetadataData::new(b"document"))
+        .set_output_data(0, Bytes::from(vec![0u8; 3]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::MetadataDataInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_metadata_cell_with_revision_error() {
+    let metadata = with_history(&MetadataData::new(b"document"), 1, [0u8; 32]);
+    let (context, tx) = CdmFixture::new().create_metadata(metadata).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::MetadataHistoryInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_metadata_cell_with_invalid_attributes_error() {
+    let mut metadata = MetadataData::new(b"document");
+    metadata.flags = METADATA_FLAG_ATTRIBUTES;
+    let (context, tx) = CdmFixture::new().create_metadata(metadata).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::MetadataAttributesInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_head_chunk_with_other_document_id_error() {
+    let (context, tx) = CdmFixture::new()
+        .create_metadata(chunk(b"first", 0, 2, [9u8; METADATA_TYPE_ARGS_LEN]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::MetadataChunkInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_create_incomplete_chunked_document_error() {
+    let fixture = CdmFixture::new();
+    let document_id = head_chunk_id(&fixture, 0);
+    let (context, tx) = fixture
+        .create_metadata(chunk(b"first", 0, 2, document_id))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::MetadataChunkCountError, 0)]
+    );
+}
+
+#[test]
+fn test_create_unordered_chunks_error() {
+    let fixture = CdmFixture::new();
+    let document_id = head_chunk_id(&fixture, 1);
+    let (context, tx) = fixture
+        .create_metadata(chunk(b"second", 1, 2, document_id))
+        .create_metadata(chunk(b"first", 0, 2, document_id))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![
+            output_type_error(Contract::Metadata, Error::MetadataChunkOrderError, 0),
+            output_type_error(Contract::Metadata, Error::MetadataChunkOrderError, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_name_error() {
+    let mut metadata = MetadataData::new(b"document");
+    metadata.name = b"renamed".to_vec();
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(metadata)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_chunk_count_error() {
+    let document_id = [7u8; METADATA_TYPE_ARGS_LEN];
+    let (context, tx) = CdmFixture::new()
+        .metadata(chunk(b"second", 1, 2, document_id))
+        .update_metadata(chunk(b"second", 1, 3, document_id))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_skipping_revision_error() {
+    let input = MetadataData::new(b"document");
+    let output = with_history(&input, 2, blake2b_256(input.to_bytes()));
+    let (context, tx) = CdmFixture::new()
+        .metadata(input)
+        .update_metadata(output)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataRevisionIncreaseError, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_with_wrong_prev_hash_error() {
+    let input = MetadataData::new(b"document");
+    let output = with_history(&input, 1, [9u8; 32]);
+    let (context, tx) = CdmFixture::new()
+        .metadata(input)
+        .update_metadata(output)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataPrevHashNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_declared_with_wrong_prev_hash_error() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(MetadataData::new(b"new document"))
+        .declare_input(1, update_metadata_action(&[9u8; 32]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::ActionPrevDataHashNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_declared_as_update_error() {
+    // Metadata updates declare the hash of the data they replace
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(MetadataData::new(b"new document"))
+        .declare_input(1, update_action())
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::ActionNotMatchTransition, 1)]
+    );
+}
+
+#[test]
+#[cfg(not(feature = "legacy-actions"))]
+fn test_update_metadata_cell_without_action_error() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(MetadataData::new(b"new document"))
+        .without_actions()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::ActionMissing, 1)]
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-actions")]
+fn test_update_metadata_cell_without_action_success() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(MetadataData::new(b"new document"))
+        .without_actions()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_frozen_metadata_cell_error() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(frozen(b"document"))
+        .update_metadata(frozen(b"new document"))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataFrozenCannotModify, 1)]
+    );
+}
+
+#[test]
+fn test_destroy_frozen_metadata_cell_error() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(frozen(b"document"))
+        .destroy_metadata()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataFrozenCannotDestroy, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_extending_history_success() {
+    let input = with_history(&MetadataData::new(b"document"), 1, [7u8; 32]);
+    let output = with_history(
+        &MetadataData::new(b"new document"),
+        2,
+        blake2b_256(input.to_bytes()),
+    );
+    let (context, tx) = CdmFixture::new()
+        .metadata(input)
+        .update_metadata(output)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_keeping_history_success() {
+    // Unchanged data and history records nothing, so the revision is not increased
+    let input = with_history(&MetadataData::new(b"document"), 1, [7u8; 32]);
+    let (context, tx) = CdmFixture::new()
+        .metadata(input.clone())
+        .update_metadata(input)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_disabling_history_error() {
+    let input = with_history(&MetadataData::new(b"document"), 1, [7u8; 32]);
+    let (context, tx) = CdmFixture::new()
+        .metadata(input)
+        .update_metadata(MetadataData::new(b"new document"))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataHistoryInvalid, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_rewriting_history_error() {
+    // The data changes but the revision of the input is kept
+    let input = with_history(&MetadataData::new(b"document"), 1, [7u8; 32]);
+    let output = with_history(
+        &MetadataData::new(b"new document"),
+        1,
+        blake2b_256(input.to_bytes()),
+    );
+    let (context, tx) = CdmFixture::new()
+        .metadata(input)
+        .update_metadata(output)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataRevisionIncreaseError, 1)]
+    );
+}
+
+#[test]
+fn test_create_metadata_cell_over_64_kib_success() {
+    // The data size is a u32, documents are not limited by the u16 size of other fields
+    let (context, tx) = CdmFixture::new()
+        .create_metadata(MetadataData::new(&vec![7u8; 100_000]))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_over_64_kib_success() {
+    // Input and output are compared in place, neither is loaded whole
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(&vec![7u8; 100_000]))
+        .update_metadata(MetadataData::new(&vec![8u8; 100_000]))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_frozen_metadata_cell_unchanged_success() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(frozen(b"document"))
+        .update_metadata(frozen(b"document"))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_with_invalid_attributes_error() {
+    let mut metadata = MetadataData::new(b"document");
+    metadata.flags = METADATA_FLAG_ATTRIBUTES;
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(metadata)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataAttributesInvalid, 1)]
+    );
+}
+
+#[test]
+fn test_create_head_chunk_completed_by_cell_dep_success() {
+    let fixture = CdmFixture::new();
+    let document_id = head_chunk_id(&fixture, 0);
+    let (context, tx) = fixture
+        .metadata(chunk(b"second", 1, 2, document_id))
+        .create_metadata(chunk(b"first", 0, 2, document_id))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_create_chunk_with_index_out_of_count_error() {
+    let fixture = CdmFixture::new();
+    let document_id = head_chunk_id(&fixture, 0);
+    let (context, tx) = fixture
+        .create_metadata(chunk(b"first", 0, 2, document_id))
+        .create_metadata(chunk(b"second", 2, 2, document_id))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![
+            output_type_error(Contract::Metadata, Error::MetadataChunkInvalid, 0),
+            output_type_error(Contract::Metadata, Error::MetadataChunkInvalid, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_create_chunks_with_different_counts_error() {
+    let fixture = CdmFixture::new();
+    let document_id = head_chunk_id(&fixture, 0);
+    let (context, tx) = fixture
+        .create_metadata(chunk(b"first", 0, 2, document_id))
+        .create_metadata(chunk(b"second", 1, 3, document_id))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![
+            output_type_error(Contract::Metadata, Error::MetadataChunkCountError, 0),
+            output_type_error(Contract::Metadata, Error::MetadataChunkCountError, 1),
+        ]
+    );
+}
+
+fn with_schema(data: &[u8], schema: &[u8]) -> MetadataData {
+    let mut metadata = MetadataData::new(data);
+    metadata.schema = Some(schema.to_vec());
+    metadata
+}
+
+#[test]
+fn test_update_metadata_cell_keeping_schema_success() {
+    // u64 attribute "age"
+    let schema = [1, 3, b'a', b'g', b'e'];
+    let (context, tx) = CdmFixture::new()
+        .metadata(with_schema(b"document", &schema))
+        .update_metadata(with_schema(b"new document", &schema))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_update_metadata_cell_schema_error() {
+    // The age attribute becomes a string, NFTs minted before would not satisfy it
+    let (context, tx) = CdmFixture::new()
+        .metadata(with_schema(b"document", &[1, 3, b'a', b'g', b'e']))
+        .update_metadata(with_schema(b"document", &[0, 3, b'a', b'g', b'e']))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_metadata_cell_removing_schema_error() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(with_schema(b"document", &[1, 3, b'a', b'g', b'e']))
+        .update_metadata(MetadataData::new(b"document"))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataImmutableFieldsNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_freeze_metadata_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(MetadataData::new(b"document"))
+        .update_metadata(frozen(b"document"))
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_unfreeze_metadata_cell_error() {
+    let (context, tx) = CdmFixture::new()
+        .metadata(frozen(b"document"))
+        .update_metadata(MetadataData::new(b"document"))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::Metadata, Error::MetadataFrozenCannotModify, 1)]
+    );
+}
+
+#[test]
+fn test_create_metadata_cell_with_type_id_of_other_output_error() {
+    let fixture = CdmFixture::new().create_metadata(MetadataData::new(b"document"));
+    let args = fixture.type_id(1)[..METADATA_TYPE_ARGS_LEN].to_vec();
+    let (context, tx) = fixture.set_output_type_args(0, &args).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::Metadata, Error::TypeArgsInvalid, 0)]
+    );
+}
@@ -0,0 +1,147 @@
+//! Systematic mutations of valid transactions, to check that the contracts reject
+//! tampered ones with the expected errors:
+//!
+//! ```ignore
+//! let (context, tx) = CdmFixture::new().issuer().class(1000).mint(1).pay(1000).build();
+//! check_mutations(&context, &tx, vec![
+//!     (
+//!         Mutation::DropOutput(1),
+//!         vec![output_type_error(Contract::NftType, Error::InvalidPaymentLockScript, 0)],
+//!     ),
+//! ])
+//! .assert_rejected();
+//! ```
+
+use ckb_testtool::ckb_error::Error as VerificationError;
+use ckb_testtool::ckb_types::{bytes::Bytes, core::TransactionView, packed::*, prelude::*};
+
+use super::context::TestContext;
+use super::fixture::MAX_CYCLES;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mutation {
+    /// Flips the bits of the data byte at offset of the output
+    FlipDataByte { output: usize, offset: usize },
+    SwapOutputs(usize, usize),
+    /// Removes the output, such as the payment cell
+    DropOutput(usize),
+    /// Replaces the lock args of the output with other args
+    ChangeLock(usize),
+    /// Appends a copy of the output, such as an NFT
+    DuplicateOutput(usize),
+    /// Flips the bits of the type args byte at offset of the output
+    AlterArgs { output: usize, offset: usize },
+}
+
+fn flip(bytes: &[u8], offset: usize) -> Bytes {
+    let mut bytes = bytes.to_vec();
+    bytes[offset] ^= 0xff;
+    Bytes::from(bytes)
+}
+
+impl Mutation {
+    /// The mutated transaction, witnesses and deps are kept
+    pub fn apply(&self, tx: &TransactionView) -> TransactionView {
+        let mut outputs: Vec<CellOutput> = tx.outputs().into_iter().collect();
+        let mut outputs_data: Vec<Bytes> = tx
+            .outputs_data()
+            .into_iter()
+            .map(|data| data.unpack())
+            .collect();
+        match *self {
+            Mutation::FlipDataByte { output, offset } => {
+                outputs_data[output] = flip(&outputs_data[output], offset);
+            }
+            Mutation::SwapOutputs(a, b) => {
+                outputs.swap(a, b);
+                outputs_data.swap(a, b);
+            }
+            Mutation::DropOutput(output) => {
+                outputs.remove(output);
+                outputs_data.remove(output);
+            }
+            Mutation::ChangeLock(output) => {
+                let lock = outputs[output].lock();
+                let args: Bytes = lock.args().unpack();
+                let args = if args.is_empty() {
+                    Bytes::from(vec![0xff])
+                } else {
+                    flip(&args, 0)
+                };
+                outputs[output] = outputs[output]
+                    .clone()
+                    .as_builder()
+                    .lock(lock.as_builder().args(args.pack()).build())
+                    .build();
+            }
+            Mutation::DuplicateOutput(output) => {
+                outputs.push(outputs[output].clone());
+                outputs_data.push(outputs_data[output].clone());
+            }
+            Mutation::AlterArgs { output, offset } => {
+                let type_ = outputs[output].type_().to_opt().expect("output type");
+                let args: Bytes = type_.args().unpack();
+                let type_ = type_.as_builder().args(flip(&args, offset).pack()).build();
+                outputs[output] = outputs[output]
+                    .clone()
+                    .as_builder()
+                    .type_(Some(type_).pack())
+                    .build();
+            }
+        }
+        tx.as_advanced_builder()
+            .set_outputs(outputs)
+            .set_outputs_data(outputs_data.into_iter().map(|data| data.pack()).collect())
+            .build()
+    }
+}
+
+/// Outcome of the mutations which were not rejected as expected
+#[derive(Debug, Default)]
+pub struct MutationReport {
+    /// Mutations which passed verification
+    pub accepted: Vec<Mutation>,
+    /// Mutations rejected with another error than the expected ones
+    pub unexpected: Vec<(Mutation, String)>,
+}
+
+impl MutationReport {
+    pub fn is_clean(&self) -> bool {
+        self.accepted.is_empty() && self.unexpected.is_empty()
+    }
+
+    pub fn assert_rejected(&self) {
+        assert!(
+            self.is_clean(),
+            "mutations accepted: {:?}, rejected with unexpected errors: {:?}",
+            self.accepted,
+            self.unexpected
+        );
+    }
+}
+
+/// Verifies every mutation of the valid transaction, each one must fail with one of its
+/// expected errors
+pub fn check_mutations(
+    context: &TestContext,
+    tx: &TransactionView,
+    cases: Vec<(Mutation, Vec<VerificationError>)>,
+) -> MutationReport {
+    context
+        .verify_tx(tx, MAX_CYCLES)
+        .expect("mutations apply to a valid transaction");
+    let mut report = MutationReport::default();
+    for (mutation, expected) in cases {
+        let mutant = mutation.apply(tx);
+        match context.verify_tx(&mutant, MAX_CYCLES) {
+            Ok(_) => report.accepted.push(mutation),
+            Err(err) => {
+                let err = err.to_string();
+                if !expected.into_iter().any(|error| error.to_string() == err) {
+                    report.unexpected.push((mutation, err));
+                }
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,102 @@
+use super::codes::{Contract, Error};
+use super::fixture::*;
+use super::mutation::*;
+use super::context::TestContext;
+use ckb_testtool::ckb_types::core::TransactionView;
+
+// The NFTs are outputs 0 and 1, the payment is output 2
+fn mint_tx() -> (TestContext, TransactionView) {
+    CdmFixture::new().issuer().class(1000).mint(2).pay(2000).build()
+}
+
+#[test]
+fn test_mint_mutations_rejected() {
+    let (context, tx) = mint_tx();
+    let either_nft = |error: Error| {
+        vec![
+            output_type_error(Contract::NftType, error, 0),
+            output_type_error(Contract::NftType, error, 1),
+        ]
+    };
+    check_mutations(
+        &context,
+        &tx,
+        vec![
+            (Mutation::SwapOutputs(0, 1), either_nft(Error::TypeArgsInvalid)),
+            (Mutation::DropOutput(2), either_nft(Error::InvalidPaymentLockScript)),
+            (Mutation::ChangeLock(2), either_nft(Error::InvalidPaymentLockScript)),
+            (
+                Mutation::DuplicateOutput(0),
+                vec![output_type_error(Contract::NftType, Error::NFTCellsCountError, 0)],
+            ),
+            (
+                Mutation::AlterArgs {
+                    output: 0,
+                    offset: 0,
+                },
+                vec![output_type_error(Contract::NftType, Error::ClassCellsCountError, 0)],
+            ),
+            (
+                Mutation::AlterArgs {
+                    output: 1,
+                    offset: 30,
+                },
+                vec![output_type_error(Contract::NftType, Error::TypeArgsInvalid, 1)],
+            ),
+        ],
+    )
+    .assert_rejected();
+}
+
+#[test]
+fn test_update_class_mutations_rejected() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .update_class(ClassData::new(2000))
+        .build();
+    // Version, cost and the first byte of the name
+    let name_offset = 1 + 8 + 2;
+    check_mutations(
+        &context,
+        &tx,
+        vec![
+            (
+                Mutation::FlipDataByte {
+                    output: 0,
+                    offset: 0,
+                },
+                vec![input_type_error(Contract::ClassType, Error::VersionInvalid, 1)],
+            ),
+            (
+                Mutation::FlipDataByte {
+                    output: 0,
+                    offset: name_offset,
+                },
+                vec![input_type_error(Contract::ClassType, Error::ClassImmutableFieldsNotSame, 1)],
+            ),
+        ],
+    )
+    .assert_rejected();
+}
+
+#[test]
+fn test_accepted_mutations_reported() {
+    let (context, tx) = mint_tx();
+    // The content of a minted NFT is free unless its class has a schema
+    let flip_content = Mutation::FlipDataByte {
+        output: 0,
+        offset: 3,
+    };
+    let report = check_mutations(
+        &context,
+        &tx,
+        vec![(
+            flip_content.clone(),
+            vec![output_type_error(Contract::NftType, Error::NFTDataInvalid, 0)],
+        )],
+    );
+
+    assert_eq!(report.accepted, vec![flip_content]);
+    assert!(report.unexpected.is_empty());
+}
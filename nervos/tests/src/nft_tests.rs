@@ -1,6 +1,10 @@
 use super::*;
-use ckb_testtool::{builtin::ALWAYS_SUCCESS, context::Context};
-use ckb_testtool::ckb_hash::Blake2bBuilder;
+use crate::assert_errors_contain;
+use super::codes::{Contract, Error};
+use super::fixture::*;
+use cdm_sdk::script_utils::helper::HashType;
+use super::context::TestContext;
+use ckb_testtool::ckb_hash::new_blake2b;
 use ckb_testtool::ckb_types::{
     bytes::Bytes,
     core::{TransactionBuilder, TransactionView,},
@@ -8,55 +12,23 @@ use ckb_testtool::ckb_types::{
     prelude::*,
 };
 
-const MAX_CYCLES: u64 = 70_000_000;
-
-const TYPE: u8 = 1;
-const CLASS_TYPE_CODE_HASH: [u8; 32] = [
-   78,   8,  42, 115, 174, 100,  11,  46,
-  187, 192, 210, 204, 177, 131, 115, 254,
-  240, 240, 143,  58, 171, 103, 241,  20,
-   16, 190, 248, 188,  64, 150,  75, 124
-];
-
-const PAYMENT_TYPE_CODE_HASH: [u8; 32] = [
-  155, 215, 224, 111,  62, 207, 75,
-  224, 242, 252, 210,  24, 139, 35,
-  241, 185, 252, 200, 142,  93, 75,
-  101, 168,  99, 123,  23, 114, 59,
-  189, 163, 204, 232
-];
-
-const PAYMENT_TYPE_ARGS: [u8; 20] = [
-   39, 148,  43, 226, 141,  38,
-   48, 178, 237, 234, 223, 164,
-  245, 142, 220, 147, 231, 104,
-  127, 126
-];
-
-fn create_test_context() -> (Context, TransactionView) {
-    // deploy contract
-    let mut context = Context::default();
-
-    let nft_bin: Bytes = Loader::default().load_binary("nft-type");
-    let nft_out_point = context.deploy_cell(nft_bin);
+fn create_test_context() -> (TestContext, TransactionView) {
+    // deploy contracts, the class type and the payment lock come from the registry
+    let mut context = TestContext::default();
+    let deployment = Deployment::deploy(&mut context);
+
+    let nft_out_point = deployment.nft_out_point.clone();
     let nft_type_script_dep = CellDep::new_builder()
         .out_point(nft_out_point.clone())
         .build();
 
-    // deploy always_success script
-    let always_success_out_point = context.deploy_cell(ALWAYS_SUCCESS.clone());
-
     // prepare scripts
-    let lock_script = context
-        .build_script(&always_success_out_point, Default::default())
-        .expect("script");
+    let lock_script = deployment.lock_script.clone();
     let lock_script_dep = CellDep::new_builder()
-        .out_point(always_success_out_point)
+        .out_point(deployment.always_success_out_point.clone())
         .build();
 
-        //////
-    let issuer_bin: Bytes = Loader::default().load_binary("issuer-type");
-    let issuer_out_point = context.deploy_cell(issuer_bin);
+    let issuer_out_point = deployment.issuer_out_point.clone();
 
     let issuer_type_args = hex::decode("157a3633c3477d84b604a25e5fca5ca681762c10").unwrap();
     let issuer_type_script = context
@@ -71,21 +43,19 @@ fn create_test_context() -> (Context, TransactionView) {
     let mut args_class_id = 8u32.to_be_bytes().to_vec();
     class_type_args.append(&mut args_class_id);
 
-    let class_aggron_type_script = Script::new_builder()
-        .code_hash(CLASS_TYPE_CODE_HASH.pack())
-        .args(Bytes::copy_from_slice(&class_type_args[..]).pack())
-        .hash_type(Byte::new(TYPE))
-        .build();
-    let class_cell_dep_aggron_out_point = context.create_cell(
+    let class_type_script = context
+        .build_script(&deployment.class_out_point, Bytes::copy_from_slice(&class_type_args[..]))
+        .expect("script");
+    let class_cell_dep_out_point = context.create_cell(
         CellOutput::new_builder()
             .capacity(2000u64.pack())
             .lock(lock_script.clone())
-            .type_(Some(class_aggron_type_script.clone()).pack())
+            .type_(Some(class_type_script.clone()).pack())
             .build(),
         class_input_data,
     );
-    let class_cell_aggron_dep = CellDep::new_builder()
-        .out_point(class_cell_dep_aggron_out_point.clone())
+    let class_cell_dep = CellDep::new_builder()
+        .out_point(class_cell_dep_out_point.clone())
         .build();
 
     // another class type script and inputs
@@ -96,21 +66,19 @@ fn create_test_context() -> (Context, TransactionView) {
     let mut args_class_id_2 = 2u32.to_be_bytes().to_vec();
     class_type_args_2.append(&mut args_class_id_2);
 
-    let class_aggron_type_script_2 = Script::new_builder()
-        .code_hash(CLASS_TYPE_CODE_HASH.pack())
-        .args(Bytes::copy_from_slice(&class_type_args_2[..]).pack())
-        .hash_type(Byte::new(TYPE))
-        .build();
-    let class_cell_dep_aggron_out_point_2 = context.create_cell(
+    let class_type_script_2 = context
+        .build_script(&deployment.class_out_point, Bytes::copy_from_slice(&class_type_args_2[..]))
+        .expect("script");
+    let class_cell_dep_out_point_2 = context.create_cell(
         CellOutput::new_builder()
             .capacity(2000u64.pack())
             .lock(lock_script.clone())
-            .type_(Some(class_aggron_type_script_2.clone()).pack())
+            .type_(Some(class_type_script_2.clone()).pack())
             .build(),
         class_input_data_2,
     );
-    let class_cell_aggron_dep_2 = CellDep::new_builder()
-        .out_point(class_cell_dep_aggron_out_point_2.clone())
+    let class_cell_dep_2 = CellDep::new_builder()
+        .out_point(class_cell_dep_out_point_2.clone())
         .build();
 
     // funding cell
@@ -129,10 +97,9 @@ fn create_test_context() -> (Context, TransactionView) {
     // nft type script and inputs
     let mut nft_type_args = class_type_args.clone().to_vec();
     
-    let first_input_previous_output = normal_input.previous_output();
-    let mut blake2b = Blake2bBuilder::new(32).build();
-    blake2b.update(first_input_previous_output.tx_hash().as_slice());
-    // Use this cell output index
+    // Type ID of this cell: hash of the first input and this cell output index
+    let mut blake2b = new_blake2b();
+    blake2b.update(normal_input.as_slice());
     let output_index = 1u64;
     blake2b.update(&output_index.to_le_bytes());
     let mut ret = [0; 32];
@@ -147,10 +114,9 @@ fn create_test_context() -> (Context, TransactionView) {
     // another nft from same class
     let mut nft_type_args_2 = class_type_args.clone().to_vec();
     
-    let first_input_previous_output = normal_input.previous_output();
-    let mut blake2b = Blake2bBuilder::new(32).build();
-    blake2b.update(first_input_previous_output.tx_hash().as_slice());
-    // Use this cell output index
+    // Type ID of this cell: hash of the first input and this cell output index
+    let mut blake2b = new_blake2b();
+    blake2b.update(normal_input.as_slice());
     let output_index = 2u64;
     blake2b.update(&output_index.to_le_bytes());
     let mut ret = [0; 32];
@@ -165,10 +131,9 @@ fn create_test_context() -> (Context, TransactionView) {
     // another nft from another class
     let mut nft_type_args_3 = class_type_args_2.clone().to_vec();
     
-    let first_input_previous_output = normal_input.previous_output();
-    let mut blake2b = Blake2bBuilder::new(32).build();
-    blake2b.update(first_input_previous_output.tx_hash().as_slice());
-    // Use this cell output index
+    // Type ID of this cell: hash of the first input and this cell output index
+    let mut blake2b = new_blake2b();
+    blake2b.update(normal_input.as_slice());
     let output_index = 3u64;
     blake2b.update(&output_index.to_le_bytes());
     let mut ret = [0; 32];
@@ -181,11 +146,8 @@ fn create_test_context() -> (Context, TransactionView) {
         .expect("script");
 
     // Payment output cell
-    let payment_lock_script = Script::new_builder()
-        .code_hash(PAYMENT_TYPE_CODE_HASH.pack())
-        .args(Bytes::copy_from_slice(&PAYMENT_TYPE_ARGS).pack())
-        .hash_type(Byte::new(TYPE))
-        .build();
+    let payment_lock_script = deployment.payment_lock.clone();
+    let registry_dep = deployment.registry_dep.clone();
     let payment_cell_output = CellOutput::new_builder()
             .capacity((100000000u64 * 2400).pack())
             .lock(payment_lock_script.clone())
@@ -219,8 +181,13 @@ fn create_test_context() -> (Context, TransactionView) {
 
     let mut witnesses = vec![];
     witnesses.push(Bytes::from(hex::decode("5500000010000000550000005500000041000000b69c542c0ee6c4b6d8350514d876ea7d8ef563e406253e959289457204447d2c4eb4e4a993073f5e76d244d2f93f7c108652e3295a9c8d72c12477e095026b9500").unwrap()));
+    // The minted nfts declare the cost of their class
+    for sale_price in [1000u64, 1000, 500].iter() {
+        let action = mint_action(sale_price * SHANNONS_PER_CKB);
+        witnesses.push(witness_args(false, None, Some(action)));
+    }
 
-    let cell_deps = vec![lock_script_dep, class_cell_aggron_dep, class_cell_aggron_dep_2, nft_type_script_dep];
+    let cell_deps = vec![lock_script_dep, registry_dep, class_cell_dep, class_cell_dep_2, nft_type_script_dep];
 
     // build transaction
     let tx = TransactionBuilder::default()
@@ -244,3 +211,623 @@ fn test_create_nft_cells_success() {
         .expect("pass verification");
     println!("consume cycles: {}", cycles);
 }
+
+#[test]
+fn test_mint_nft_cells_with_fixture() {
+    let (context, tx) = CdmFixture::new().issuer().class(1000).mint(2).pay(2000).build();
+
+    let cycles = context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+    println!("consume cycles: {}", cycles);
+}
+
+fn schema_metadata() -> MetadataData {
+    let mut metadata = MetadataData::new(b"schema");
+    // u64 attribute "age"
+    metadata.schema = Some(vec![1, 3, b'a', b'g', b'e']);
+    metadata
+}
+
+#[test]
+fn test_mint_nft_cells_matching_schema_success() {
+    let mut age = vec![1, 0, 12, 3, b'a', b'g', b'e'];
+    age.extend_from_slice(&42u64.to_be_bytes());
+    let fixture = CdmFixture::new().issuer().metadata(schema_metadata());
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = fixture.metadata_type_hash();
+    let (context, tx) = fixture.class_with(class).mint_with(1, &age).pay(1000).build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_transfer_nft_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_claim_nft_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .declare_input(1, claim_action())
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_claim_nft_cell_keeping_lock_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .update_nft(b"nft")
+        .declare_input(1, claim_action())
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::NftType, Error::ActionNotMatchTransition, 1)]
+    );
+}
+
+#[test]
+fn test_lock_nft_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .update_nft(b"nft")
+        .declare_input(1, lock_action())
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+#[cfg(not(feature = "legacy-actions"))]
+fn test_transfer_nft_cell_without_action_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .without_actions()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(err, vec![input_type_error(Contract::NftType, Error::ActionMissing, 1)]);
+}
+
+#[test]
+#[cfg(feature = "legacy-actions")]
+fn test_transfer_nft_cell_without_action_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .without_actions()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+#[cfg(not(feature = "legacy-actions"))]
+fn test_mint_nft_cell_without_action_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .without_actions()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::ActionMissing, 0)]
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-actions")]
+fn test_mint_nft_cell_without_action_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .without_actions()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_burn_nft_cell_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .burn()
+        .declare_input(1, burn_action())
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_mint_two_nft_cells_with_same_type_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .duplicate_output(0)
+        .pay(2000)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::NFTCellsCountError, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_with_invalid_type_id_error() {
+    let fixture = CdmFixture::new().issuer().class(1000).mint(1).pay(1000);
+    let class_args: Bytes = fixture.class_type().expect("class").args().unpack();
+    let mut args = class_args.to_vec();
+    args.extend_from_slice(&[0u8; 32]);
+    let (context, tx) = fixture.set_output_type_args(0, &args).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::TypeArgsInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_with_type_id_of_other_output_error() {
+    // Each NFT must carry the type id of its own output index, here the payment one
+    let fixture = CdmFixture::new().issuer().class(1000).mint(2).pay(2000);
+    let class_args: Bytes = fixture.class_type().expect("class").args().unpack();
+    let mut args = class_args.to_vec();
+    args.extend_from_slice(&fixture.type_id(2));
+    let (context, tx) = fixture.set_output_type_args(0, &args).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::TypeArgsInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_without_class_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .without_class_dep()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::ClassCellsCountError, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_declared_without_class_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .without_class_dep()
+        .declare_output(0, mint_action(1000 * SHANNONS_PER_CKB))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::ClassCellsCountError, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_declared_with_wrong_sale_price_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .declare_output(0, mint_action(1))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::ActionSalePriceInvalid, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_with_class_registered_by_other_data_hash_type_success() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .with_registry_hash_type(HashType::Data2 as u8)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_mint_nft_cell_with_class_registered_by_type_hash_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .with_registry_hash_type(HashType::Type as u8)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::ClassCellsCountError, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_without_registry_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1000)
+        .without_registry()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::RegistryCellMissing, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_without_payment_error() {
+    let (context, tx) = CdmFixture::new().issuer().class(1000).mint(1).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::InvalidPaymentLockScript, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_with_small_payment_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .mint(1)
+        .pay(1)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::PaymentNotEnough, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_without_metadata_cell_error() {
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = Some([7u8; 32]);
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class_with(class)
+        .mint(1)
+        .pay(1000)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::NFTMetadataCellMissing, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_with_unfrozen_metadata_error() {
+    let fixture = CdmFixture::new().issuer().metadata(MetadataData::new(b"document"));
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = fixture.metadata_type_hash();
+    class.flags = CLASS_FLAG_REQUIRE_FROZEN_METADATA;
+    let (context, tx) = fixture.class_with(class).mint(1).pay(1000).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::NFTMetadataNotFrozen, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cell_with_frozen_metadata_success() {
+    let mut metadata = MetadataData::new(b"document");
+    metadata.flags = METADATA_FLAG_FROZEN;
+    let fixture = CdmFixture::new().issuer().metadata(metadata);
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = fixture.metadata_type_hash();
+    class.flags = CLASS_FLAG_REQUIRE_FROZEN_METADATA;
+    let (context, tx) = fixture.class_with(class).mint(1).pay(1000).build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_mint_nft_cell_not_matching_schema_error() {
+    let fixture = CdmFixture::new().issuer().metadata(schema_metadata());
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = fixture.metadata_type_hash();
+    let (context, tx) = fixture.class_with(class).mint(1).pay(1000).build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::NFTAttributesNotMatchSchema, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cells_with_last_not_matching_schema_error() {
+    // The batch is validated by the group of the first minted nft only
+    let mut age = vec![1, 0, 12, 3, b'a', b'g', b'e'];
+    age.extend_from_slice(&42u64.to_be_bytes());
+    let fixture = CdmFixture::new().issuer().metadata(schema_metadata());
+    let mut class = ClassData::new(1000);
+    class.metadata_type_hash = fixture.metadata_type_hash();
+    let (context, tx) = fixture
+        .class_with(class)
+        .mint_with(2, &age)
+        .mint(1)
+        .pay(3000)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::NFTAttributesNotMatchSchema, 0)]
+    );
+}
+
+#[test]
+fn test_mint_nft_cells_with_overflowing_cost_error() {
+    // The cost of one nft fits in shannons, the cost of two does not
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(u64::MAX / SHANNONS_PER_CKB)
+        .mint(2)
+        .pay(1000)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![output_type_error(Contract::NftType, Error::NFTCostOverflow, 0)]
+    );
+}
+
+#[test]
+fn test_update_nft_cell_data_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .update_nft(b"changed")
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::NftType, Error::NFTDataNotSame, 1)]
+    );
+}
+
+#[test]
+fn test_update_nft_cell_with_short_data_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .set_output_data(0, Bytes::from(vec![0u8; 2]))
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::NftType, Error::NFTDataInvalid, 1)]
+    );
+}
+
+#[test]
+fn test_transfer_nft_cell_without_owner_signature_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::NftType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_transfer_nft_cell_by_delegating_class_owner_success() {
+    // The funding input is locked by the lock of the class cell
+    let mut class = ClassData::new(1000);
+    class.flags = CLASS_FLAG_OWNER_DELEGATE;
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class_with(class)
+        .nft(b"nft")
+        .transfer()
+        .class_dep()
+        .without_signatures()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_transfer_nft_cell_without_registry_success() {
+    // Only a class with the owner delegate flag needs the registry to be confirmed
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .class_dep()
+        .without_registry()
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_transfer_nft_cell_by_delegating_class_owner_without_registry_error() {
+    let mut class = ClassData::new(1000);
+    class.flags = CLASS_FLAG_OWNER_DELEGATE;
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class_with(class)
+        .nft(b"nft")
+        .transfer()
+        .class_dep()
+        .without_signatures()
+        .without_registry()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::NftType, Error::RegistryCellMissing, 1)]
+    );
+}
+
+#[test]
+fn test_transfer_nft_cell_by_class_owner_without_delegate_error() {
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"nft")
+        .transfer()
+        .class_dep()
+        .without_signatures()
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![input_type_error(Contract::NftType, Error::OwnerAuthorizationMissing, 1)]
+    );
+}
+
+#[test]
+fn test_mixed_transaction_success() {
+    // Burns an NFT, mints another of the same class, creates a metadata cell and keeps
+    // the issuer, every group matches its own cells
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"old")
+        .burn()
+        .declare_input(1, burn_action())
+        .mint(1)
+        .declare_output(0, mint_action(1000 * SHANNONS_PER_CKB))
+        .pay(1000)
+        .create_metadata(MetadataData::new(b"document"))
+        .update_issuer(1)
+        .build();
+
+    context
+        .verify_tx(&tx, MAX_CYCLES)
+        .expect("pass verification");
+}
+
+#[test]
+fn test_mixed_transaction_with_mismatched_action_error() {
+    // The burn is declared on the minted NFT and the mint on the burned one
+    let (context, tx) = CdmFixture::new()
+        .issuer()
+        .class(1000)
+        .nft(b"old")
+        .burn()
+        .declare_input(1, mint_action(1000 * SHANNONS_PER_CKB))
+        .mint(1)
+        .declare_output(0, burn_action())
+        .pay(1000)
+        .build();
+
+    let err = context.verify_tx(&tx, MAX_CYCLES).unwrap_err();
+    assert_errors_contain!(
+        err,
+        vec![
+            input_type_error(Contract::NftType, Error::ActionNotMatchTransition, 1),
+            output_type_error(Contract::NftType, Error::ActionNotMatchTransition, 0),
+        ]
+    );
+}
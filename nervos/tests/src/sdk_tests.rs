@@ -0,0 +1,208 @@
+use super::chain::*;
+use super::fixture::*;
+use cdm_sdk::script_utils::{
+    class::Class, issuer::Issuer, metadata::Metadata, nft::Nft, registry::Registry,
+};
+use cdm_sdk::{
+    CdmTxBuilder, ContractRef, Deployment as SdkDeployment, Error, Funding, RegistryCell,
+};
+use ckb_testtool::ckb_hash::blake2b_256;
+use ckb_testtool::ckb_types::{packed::*, prelude::*};
+
+const FEE: u64 = 1_000;
+
+fn contract_ref(chain: &mut Chain, out_point: OutPoint) -> ContractRef {
+    let script = chain.build_script(&out_point, &[]);
+    ContractRef {
+        code_hash: script.code_hash().unpack(),
+        hash_type: script.hash_type().as_slice()[0],
+        cell_dep: CellDep::new_builder().out_point(out_point).build(),
+    }
+}
+
+fn sdk_deployment(chain: &mut Chain) -> SdkDeployment {
+    let deployment = chain.deployment().clone();
+    let (_, registry_data) = chain
+        .context()
+        .get_cell(&deployment.registry_dep.out_point())
+        .expect("registry cell");
+    SdkDeployment {
+        issuer_type: contract_ref(chain, deployment.issuer_out_point.clone()),
+        class_type: contract_ref(chain, deployment.class_out_point.clone()),
+        nft_type: contract_ref(chain, deployment.nft_out_point.clone()),
+        metadata_type: contract_ref(chain, deployment.metadata_out_point.clone()),
+        registry: RegistryCell {
+            cell_dep: deployment.registry_dep.clone(),
+            registry: Registry::from_data(&registry_data).expect("registry"),
+        },
+    }
+}
+
+fn sdk_cell(cell: &LiveCell) -> cdm_sdk::LiveCell {
+    cdm_sdk::LiveCell {
+        out_point: cell.out_point.clone(),
+        output: cell.output.clone(),
+        data: cell.data.clone(),
+    }
+}
+
+fn funding(chain: &mut Chain) -> Funding {
+    let cell = chain.fund();
+    Funding {
+        change_lock: cell.output.lock(),
+        cells: vec![sdk_cell(&cell)],
+        fee: FEE,
+    }
+}
+
+#[test]
+fn test_sdk_nft_lifecycle_success() {
+    let mut chain = Chain::new();
+    let deployment = sdk_deployment(&mut chain);
+    let builder = CdmTxBuilder::new(&deployment);
+    let owner_lock = chain.deployment().lock_script.clone();
+    let receiver_lock = chain.deployment().receiver_lock.clone();
+
+    let tx = builder
+        .create_issuer(b"issuer", owner_lock.clone(), &funding(&mut chain))
+        .expect("build create issuer");
+    let issuer = chain.commit(tx).expect("create issuer")[0].clone();
+
+    let class = Class::from_data(&ClassData::new(100).to_bytes()).expect("class");
+    let tx = builder
+        .create_class(&sdk_cell(&issuer), &class, &funding(&mut chain))
+        .expect("build create class");
+    let cells = chain.commit(tx).expect("create class");
+    let (issuer, class) = (cells[0].clone(), cells[1].clone());
+    assert_eq!(
+        Issuer::from_data(&issuer.data).expect("issuer").class_count,
+        1
+    );
+
+    let tx = builder
+        .mint(
+            &sdk_cell(&class),
+            None,
+            &[&b"first"[..], &b"second"[..]],
+            owner_lock,
+            &funding(&mut chain),
+        )
+        .expect("build mint");
+    let nfts = chain.commit(tx).expect("mint");
+    assert_eq!(
+        Nft::from_data(&nfts[1].data).expect("nft").data_content(),
+        b"second"
+    );
+
+    let tx = builder
+        .transfer(
+            &sdk_cell(&nfts[0]),
+            receiver_lock.clone(),
+            &funding(&mut chain),
+        )
+        .expect("build transfer");
+    let nft = chain.commit(tx).expect("transfer")[0].clone();
+    assert_eq!(nft.output.lock().as_slice(), receiver_lock.as_slice());
+
+    let tx = builder
+        .burn(&sdk_cell(&nft), &funding(&mut chain))
+        .expect("build burn");
+    chain.commit(tx).expect("burn");
+    assert!(!chain.is_live(&nft.out_point));
+    assert!(chain.is_live(&nfts[1].out_point));
+}
+
+#[test]
+fn test_sdk_mint_with_metadata_success() {
+    let mut chain = Chain::new();
+    let deployment = sdk_deployment(&mut chain);
+    let builder = CdmTxBuilder::new(&deployment);
+    let owner_lock = chain.deployment().lock_script.clone();
+
+    let metadata = chain
+        .create_metadata(MetadataData::new(b"document"))
+        .expect("create metadata");
+    let metadata_type = metadata.output.type_().to_opt().expect("metadata type");
+    let issuer = chain.create_issuer().expect("create issuer");
+    let mut class = ClassData::new(100);
+    class.metadata_type_hash = Some(metadata_type.calc_script_hash().unpack());
+    let (_, class) = chain.create_class(&issuer, class).expect("create class");
+
+    let tx = builder
+        .mint(
+            &sdk_cell(&class),
+            Some(&sdk_cell(&metadata)),
+            &[&b"nft"[..]],
+            owner_lock,
+            &funding(&mut chain),
+        )
+        .expect("build mint");
+    assert!(tx
+        .cell_deps()
+        .into_iter()
+        .any(|cell_dep| cell_dep.out_point() == metadata.out_point));
+    let nfts = chain.commit(tx).expect("mint");
+    assert_eq!(Nft::from_data(&nfts[0].data).expect("nft").data_content(), b"nft");
+    assert!(chain.is_live(&metadata.out_point));
+}
+
+#[test]
+fn test_sdk_update_metadata_success() {
+    let mut chain = Chain::new();
+    let deployment = sdk_deployment(&mut chain);
+    let builder = CdmTxBuilder::new(&deployment);
+
+    // Without history the flags stay unset
+    let metadata = chain
+        .create_metadata(MetadataData::new(b"document"))
+        .expect("create metadata");
+    let tx = builder
+        .update_metadata(&sdk_cell(&metadata), b"new document", &funding(&mut chain))
+        .expect("build update metadata");
+    let updated = chain.commit(tx).expect("update metadata")[0].clone();
+    let decoded = Metadata::from_data(&updated.data).expect("metadata");
+    assert_eq!(decoded.data_content(), b"new document");
+    assert_eq!(decoded.flags, 0);
+    assert!(decoded.history.is_none());
+
+    // With history the previous data becomes the next revision
+    let mut data = MetadataData::new(b"document");
+    data.history = Some((0, [0u8; 32]));
+    let metadata = chain.create_metadata(data).expect("create metadata");
+    let tx = builder
+        .update_metadata(&sdk_cell(&metadata), b"new document", &funding(&mut chain))
+        .expect("build update metadata");
+    let updated = chain.commit(tx).expect("update metadata")[0].clone();
+    let decoded = Metadata::from_data(&updated.data).expect("metadata");
+    assert_eq!(decoded.flags, METADATA_FLAG_HISTORY);
+    let history = decoded.history.expect("history");
+    assert_eq!(history.revision, 1);
+    assert_eq!(history.prev_data_hash, blake2b_256(&metadata.data));
+}
+
+#[test]
+fn test_sdk_capacity_not_enough_error() {
+    let mut chain = Chain::new();
+    let deployment = sdk_deployment(&mut chain);
+    let builder = CdmTxBuilder::new(&deployment);
+    let owner_lock = chain.deployment().lock_script.clone();
+
+    let mut funding = funding(&mut chain);
+    let capacity: u64 = funding.cells[0].output.capacity().unpack();
+    funding.fee = capacity;
+    match builder.create_issuer(b"issuer", owner_lock, &funding) {
+        Err(Error::CapacityNotEnough { available, .. }) => assert_eq!(available, capacity),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_sdk_wrong_cell_error() {
+    let mut chain = Chain::new();
+    let deployment = sdk_deployment(&mut chain);
+    let builder = CdmTxBuilder::new(&deployment);
+    let issuer = chain.create_issuer().expect("create issuer");
+
+    let result = builder.transfer(&sdk_cell(&issuer), Script::default(), &funding(&mut chain));
+    assert_eq!(result.err(), Some(Error::CellInvalid("not an NFT cell")));
+}
@@ -0,0 +1,198 @@
+//! Native execution of the contracts with ckb-x64-simulator, to step through them in a
+//! debugger and collect line coverage.
+//!
+//! The contracts are built as executables with their `simulator` feature. For every
+//! script group running one of them, the transaction is dumped as a mock transaction to
+//! `CKB_TX_FILE` and the group is described in `CKB_RUNNING_SETUP`, from which the
+//! simulator serves the syscalls. The exit code of the executable is the script result.
+
+use ckb_jsonrpc_types as json_types;
+use ckb_testtool::ckb_error::Error as VerificationError;
+use ckb_testtool::ckb_script::ScriptError;
+use ckb_testtool::ckb_types::{core::TransactionView, packed::*, prelude::*};
+use ckb_testtool::context::Context;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const TX_FILE_ENV_VAR: &str = "CKB_TX_FILE";
+const RUNNING_SETUP_ENV_VAR: &str = "CKB_RUNNING_SETUP";
+const TYPE_HASH_TYPE: u8 = 1;
+
+/// Type script group of a transaction, identified by its first cell
+struct ScriptGroup {
+    script: Script,
+    is_output: bool,
+    index: usize,
+}
+
+impl ScriptGroup {
+    fn running_setup(&self) -> String {
+        format!(
+            r#"{{"is_lock_script":false,"is_output":{},"script_index":{},"native_binaries":{{}}}}"#,
+            self.is_output, self.index
+        )
+    }
+
+    fn error(&self, error: ScriptError) -> VerificationError {
+        if self.is_output {
+            error.output_type_script(self.index).into()
+        } else {
+            error.input_type_script(self.index).into()
+        }
+    }
+}
+
+// Resolved cell in the mock transaction format of ckb-debugger, read by the simulator
+fn mock_cell(context: &Context, out_point: &OutPoint, key: &str, value: Value) -> Value {
+    let (output, data) = context.get_cell(out_point).expect("resolved cell");
+    json!({
+        key: value,
+        "output": json_types::CellOutput::from(output),
+        "data": json_types::JsonBytes::from_bytes(data),
+        "header": null,
+    })
+}
+
+/// The transaction with its resolved inputs and cell deps. Headers are not included,
+/// the contracts do not load them.
+fn mock_tx(context: &Context, tx: &TransactionView) -> Value {
+    let inputs: Vec<Value> = tx
+        .inputs()
+        .into_iter()
+        .map(|input| {
+            let out_point = input.previous_output();
+            mock_cell(context, &out_point, "input", json!(json_types::CellInput::from(input)))
+        })
+        .collect();
+    let cell_deps: Vec<Value> = tx
+        .cell_deps()
+        .into_iter()
+        .map(|cell_dep| {
+            let out_point = cell_dep.out_point();
+            mock_cell(context, &out_point, "cell_dep", json!(json_types::CellDep::from(cell_dep)))
+        })
+        .collect();
+    json!({
+        "mock_info": {
+            "inputs": inputs,
+            "cell_deps": cell_deps,
+            "header_deps": [],
+        },
+        "tx": json_types::Transaction::from(tx.data()),
+    })
+}
+
+/// Native executables by the data hash of the deployed cell, and the data hashes of the
+/// other deployed scripts, which are not run
+#[derive(Default)]
+pub struct Simulator {
+    binaries: HashMap<Byte32, PathBuf>,
+    external_scripts: HashSet<Byte32>,
+}
+
+impl Simulator {
+    pub fn register(&mut self, code_hash: Byte32, path: PathBuf) {
+        self.binaries.insert(code_hash, path);
+    }
+
+    /// Registers a script without native executable, such as always success, whose groups
+    /// are not run
+    pub fn register_external(&mut self, code_hash: Byte32) {
+        self.external_scripts.insert(code_hash);
+    }
+
+    // Data hash of the code cell of the script, a type hash selects the cell dep by the
+    // hash of its type script
+    fn code_data_hash(context: &Context, tx: &TransactionView, script: &Script) -> Option<Byte32> {
+        let hash_type: u8 = script.hash_type().into();
+        if hash_type != TYPE_HASH_TYPE {
+            return Some(script.code_hash());
+        }
+        tx.cell_deps().into_iter().find_map(|cell_dep| {
+            let (output, data) = context.get_cell(&cell_dep.out_point())?;
+            let type_hash = output.type_().to_opt()?.calc_script_hash();
+            if type_hash == script.code_hash() {
+                Some(CellOutput::calc_data_hash(&data))
+            } else {
+                None
+            }
+        })
+    }
+
+    // Type groups in the order of the VM, those with inputs are reported by input index
+    fn type_groups(context: &Context, tx: &TransactionView) -> Vec<ScriptGroup> {
+        let input_types = tx.inputs().into_iter().map(|input| {
+            let (output, _) = context
+                .get_cell(&input.previous_output())
+                .expect("input cell");
+            output.type_().to_opt()
+        });
+        let output_types = tx.outputs().into_iter().map(|output| output.type_().to_opt());
+
+        let mut groups: Vec<ScriptGroup> = Vec::new();
+        let cells = input_types
+            .enumerate()
+            .map(|(index, type_)| (type_, false, index))
+            .chain(output_types.enumerate().map(|(index, type_)| (type_, true, index)));
+        for (type_, is_output, index) in cells {
+            let script = match type_ {
+                Some(script) => script,
+                None => continue,
+            };
+            if groups.iter().all(|group| group.script.as_slice() != script.as_slice()) {
+                groups.push(ScriptGroup {
+                    script,
+                    is_output,
+                    index,
+                });
+            }
+        }
+        groups
+    }
+
+    /// Runs the groups of the registered contracts natively and returns the first failure.
+    /// Groups of external scripts are skipped, groups of any other script fail as their
+    /// code is not deployed.
+    pub fn verify_tx(&self, context: &Context, tx: &TransactionView) -> Result<(), VerificationError> {
+        let tx_file = env::temp_dir().join(format!("cdm-simulator-{}.json", tx.hash()));
+        fs::write(&tx_file, mock_tx(context, tx).to_string()).expect("write mock tx");
+
+        let mut result = Ok(());
+        for group in Self::type_groups(context, tx) {
+            let code_hash = Self::code_data_hash(context, tx, &group.script);
+            let binary = match code_hash.as_ref().and_then(|hash| self.binaries.get(hash)) {
+                Some(binary) => binary,
+                None if code_hash.map_or(false, |hash| self.external_scripts.contains(&hash)) => {
+                    continue
+                }
+                None => {
+                    result = Err(group.error(ScriptError::InvalidCodeHash));
+                    break;
+                }
+            };
+            let status = Command::new(binary)
+                .env(TX_FILE_ENV_VAR, &tx_file)
+                .env(RUNNING_SETUP_ENV_VAR, group.running_setup())
+                .status()
+                .expect("run native contract");
+            // Exit codes are the i8 error codes truncated to u8, None when killed
+            match status.code() {
+                Some(0) => {}
+                Some(code) => {
+                    result = Err(group.error(ScriptError::ValidationFailure(code as i8)));
+                    break;
+                }
+                None => {
+                    result = Err(group.error(ScriptError::ValidationFailure(-1)));
+                    break;
+                }
+            }
+        }
+        let _ = fs::remove_file(&tx_file);
+        result
+    }
+}